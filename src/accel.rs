@@ -3,6 +3,8 @@
 
 use core::ops::*;
 
+use crate::units::{ MilliG, MetersPerSecondSquared, Vector3 };
+
 use micro::drivers::Data;
 
 
@@ -14,16 +16,44 @@ pub trait Accelerometer {
     /// Returns the raw accelerometer data.
     fn accelraw(&mut self) -> Result<[Self::Output; 3], Self::Error>;
 
-    /// Returns the accelerometer data after a normalization process.
+    /// Returns the accelerometer data in m/s^2 after a normalization process.
     /// The associated `F` type must be an `f32` wrapper with platform support
     /// for `f32` operations.
     /// For example, if the ISA does not define float support, but the device
     /// has a propietary peripheral to operate on `f32`, this wrapper must
     /// implement this functionality.
     /// In case of hardware support for `f32` (e.g. amr-none-eabihf) `F=f32`.
-    fn accel<F>(&mut self) -> Result<[F; 3], Self::Error>
+    fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, Self::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the accelerometer data in mg with integer only arithmetic.
+    /// Applies the same saturation handling as the normalization process.
+    fn accel_mg(&mut self) -> Result<Vector3<MilliG>, Self::Error>;
+}
+
+
+
+/// Async version of the `Accelerometer` trait.
+/// The normalization follows the same rules as the blocking trait.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncAccelerometer {
+    type Error;
+    type Output: Data;
+
+    /// Returns the raw accelerometer data.
+    async fn accelraw(&mut self) -> Result<[Self::Output; 3], Self::Error>;
+
+    /// Returns the accelerometer data in m/s^2 after a normalization process.
+    async fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, Self::Error>
         where F: Clone + Copy +
             From<f32> + From<Self::Output> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the accelerometer data in mg with integer only arithmetic.
+    async fn accel_mg(&mut self) -> Result<Vector3<MilliG>, Self::Error>;
 }
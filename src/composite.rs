@@ -0,0 +1,329 @@
+//! IMU composed of separate accelerometer / magnetometer and gyroscope /
+//! thermometer devices (e.g. LSM303DLHC and L3GD20 in the STM32F3-Discovery).
+//! Only the accelerometer and the gyroscope are required, the magnetometer
+//! and the thermometer are read when selected with `with_magnetometer` and
+//! `with_thermometer`.
+
+
+use crate::{
+    Accelerometer, Gyroscope, Magnetometer, Thermometer,
+    Clock, Imu, ImuSample,
+};
+use crate::units::{ Celsius, MicroTesla, Vector3 };
+
+use core::marker::PhantomData;
+use core::ops::*;
+
+
+
+/// Errors that can occur with a composite IMU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<A, G> {
+    /// An error ocurred in the accelerometer / magnetometer device.
+    Accel(A),
+
+    /// An error ocurred in the gyroscope / thermometer device.
+    Gyro(G),
+}
+
+
+
+/// Marker of an optional sensor that is read into the IMU samples.
+#[derive(Debug, Clone, Copy)]
+pub struct Enabled;
+
+/// Marker of an optional sensor that is not read, its field of the IMU
+/// samples is `None`.
+#[derive(Debug, Clone, Copy)]
+pub struct Disabled;
+
+
+
+/// Accelerometer device that may also provide the magnetic field.
+/// Implemented for every accelerometer with `Disabled` and for every
+/// accelerometer and magnetometer device with `Enabled`.
+pub trait OptionalMagnetometer<F, S>: Accelerometer {
+    /// Returns the magnetic field if the magnetometer is enabled.
+    fn optional_mag(&mut self) -> Result<Option<Vector3<MicroTesla<F>>>, Self::Error>;
+}
+
+impl<F, A: Accelerometer> OptionalMagnetometer<F, Disabled> for A {
+    fn optional_mag(&mut self) -> Result<Option<Vector3<MicroTesla<F>>>, A::Error> {
+        Ok(None)
+    }
+}
+
+impl<F, A> OptionalMagnetometer<F, Enabled> for A
+    where
+        A: Accelerometer + Magnetometer<Error = <A as Accelerometer>::Error>,
+        F: Clone + Copy +
+            From<f32> + From<<A as Magnetometer>::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+{
+    fn optional_mag(&mut self) -> Result<Option<Vector3<MicroTesla<F>>>, <A as Accelerometer>::Error> {
+        Ok( Some( self.mag()? ) )
+    }
+}
+
+/// Gyroscope device that may also provide the die temperature.
+/// Implemented for every gyroscope with `Disabled` and for every gyroscope
+/// and thermometer device with `Enabled`.
+pub trait OptionalThermometer<F, S>: Gyroscope {
+    /// Returns the die temperature if the thermometer is enabled.
+    fn optional_temp(&mut self) -> Result<Option<Celsius<F>>, Self::Error>;
+}
+
+impl<F, G: Gyroscope> OptionalThermometer<F, Disabled> for G {
+    fn optional_temp(&mut self) -> Result<Option<Celsius<F>>, G::Error> {
+        Ok(None)
+    }
+}
+
+impl<F, G> OptionalThermometer<F, Enabled> for G
+    where
+        G: Gyroscope + Thermometer<Error = <G as Gyroscope>::Error>,
+        F: Clone + Copy +
+            From<f32> + From<<G as Thermometer>::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+{
+    fn optional_temp(&mut self) -> Result<Option<Celsius<F>>, <G as Gyroscope>::Error> {
+        Ok( Some( self.temp()? ) )
+    }
+}
+
+
+
+/// IMU composed of an accelerometer / magnetometer device and a gyroscope /
+/// thermometer device.
+/// The `M` and `T` markers select if the magnetometer and the thermometer
+/// are read.
+pub struct Composite<A, G, C, M = Disabled, T = Disabled> {
+    /// Accelerometer and magnetometer device.
+    accel: A,
+
+    /// Gyroscope and thermometer device.
+    gyro: G,
+
+    /// Timestamp source.
+    clock: C,
+
+    /// Selection of the optional sensors.
+    sensors: PhantomData<(M, T)>,
+}
+
+impl<A, G, C> Composite<A, G, C> {
+    /// Creates a new 6-DoF composite IMU from already configured devices.
+    /// The magnetometer and the thermometer are not read.
+    pub fn new(accel: A, gyro: G, clock: C) -> Self {
+        Composite { accel, gyro, clock, sensors: PhantomData }
+    }
+}
+
+impl<A, G, C, M, T> Composite<A, G, C, M, T> {
+    /// Reads the magnetometer of the accelerometer device into the samples.
+    pub fn with_magnetometer(self) -> Composite<A, G, C, Enabled, T> {
+        Composite { accel: self.accel, gyro: self.gyro, clock: self.clock, sensors: PhantomData }
+    }
+
+    /// Reads the thermometer of the gyroscope device into the samples.
+    pub fn with_thermometer(self) -> Composite<A, G, C, M, Enabled> {
+        Composite { accel: self.accel, gyro: self.gyro, clock: self.clock, sensors: PhantomData }
+    }
+
+    /// Gives access to the accelerometer / magnetometer device.
+    pub fn accelerometer(&mut self) -> &mut A {
+        &mut self.accel
+    }
+
+    /// Gives access to the gyroscope / thermometer device.
+    pub fn gyroscope(&mut self) -> &mut G {
+        &mut self.gyro
+    }
+
+    /// Releases the devices and the clock.
+    pub fn release(self) -> (A, G, C) {
+        (self.accel, self.gyro, self.clock)
+    }
+}
+
+impl<F, A, G, C, M, T> Imu<F> for Composite<A, G, C, M, T>
+    where
+        A: Accelerometer + OptionalMagnetometer<F, M>,
+        G: Gyroscope + OptionalThermometer<F, T>,
+        C: Clock,
+        F: Clone + Copy +
+            From<f32> +
+            From<<A as Accelerometer>::Output> + From<<G as Gyroscope>::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+{
+    type Error = Error<<A as Accelerometer>::Error, <G as Gyroscope>::Error>;
+    type Instant = C::Instant;
+
+    /// Reads the accelerometer, gyroscope and, if enabled, the magnetometer
+    /// and the gyroscope die temperature, in that order.
+    /// The timestamp is taken right before the accelerometer read.
+    fn sample(&mut self) -> Result<ImuSample<F, C::Instant>, Self::Error> {
+        let timestamp = self.clock.now();
+
+        let accel = match self.accel.accel() {
+            Err(e) => return Err( Error::Accel(e) ),
+            Ok(data) => data,
+        };
+
+        let gyro = match self.gyro.gyro() {
+            Err(e) => return Err( Error::Gyro(e) ),
+            Ok(data) => data,
+        };
+
+        let mag = match self.accel.optional_mag() {
+            Err(e) => return Err( Error::Accel(e) ),
+            Ok(data) => data,
+        };
+
+        let temp = match self.gyro.optional_temp() {
+            Err(e) => return Err( Error::Gyro(e) ),
+            Ok(data) => data,
+        };
+
+        Ok( ImuSample { timestamp, accel, gyro, mag, temp } )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::{ MetersPerSecondSquared, MilliCelsius, MilliDegreesPerSecond, MilliG, NanoTesla, RadiansPerSecond };
+
+    /// Accelerometer and magnetometer that reads constant values.
+    struct Accel;
+
+    /// Gyroscope without thermometer that reads constant values.
+    struct Gyro;
+
+    /// Gyroscope and thermometer that reads constant values.
+    struct GyroTemp;
+
+    /// Clock that counts the samples.
+    struct Counter(u32);
+
+    impl Accelerometer for Accel {
+        type Error = ();
+        type Output = i16;
+
+        fn accelraw(&mut self) -> Result<[i16; 3], ()> { Ok([0, 0, 1000]) }
+
+        fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, ()>
+            where F: Clone + Copy + From<f32> + From<i16> +
+                Add<F, Output=F> + Sub<F, Output=F> + Mul<F, Output=F> + Div<F, Output=F>
+        {
+            Ok( Vector3::new(0.0, 0.0, 9.8).map(|a: f32| MetersPerSecondSquared(F::from(a))) )
+        }
+
+        fn accel_mg(&mut self) -> Result<Vector3<MilliG>, ()> { Ok( Vector3::new(0, 0, 1000).map(MilliG) ) }
+    }
+
+    impl Magnetometer for Accel {
+        type Error = ();
+        type Output = i16;
+
+        fn magraw(&mut self) -> Result<[i16; 3], ()> { Ok([110, 0, 0]) }
+
+        fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, ()>
+            where F: Clone + Copy + From<f32> + From<i16> +
+                Add<F, Output=F> + Sub<F, Output=F> + Mul<F, Output=F> + Div<F, Output=F>
+        {
+            Ok( Vector3::new(10.0, 0.0, 0.0).map(|m: f32| MicroTesla(F::from(m))) )
+        }
+
+        fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, ()> { Ok( Vector3::new(10_000, 0, 0).map(NanoTesla) ) }
+    }
+
+    impl Gyroscope for Gyro {
+        type Error = ();
+        type Output = i16;
+
+        fn gyroraw(&mut self) -> Result<[i16; 3], ()> { Ok([0, 100, 0]) }
+
+        fn gyro<F>(&mut self) -> Result<Vector3<RadiansPerSecond<F>>, ()>
+            where F: Clone + Copy + From<f32> + From<i16> +
+                Add<F, Output=F> + Sub<F, Output=F> + Mul<F, Output=F> + Div<F, Output=F>
+        {
+            Ok( Vector3::new(0.0, 1.0, 0.0).map(|g: f32| RadiansPerSecond(F::from(g))) )
+        }
+
+        fn gyro_mdps(&mut self) -> Result<Vector3<MilliDegreesPerSecond>, ()> { Ok( Vector3::new(0, 875, 0).map(MilliDegreesPerSecond) ) }
+    }
+
+    impl Gyroscope for GyroTemp {
+        type Error = ();
+        type Output = i16;
+
+        fn gyroraw(&mut self) -> Result<[i16; 3], ()> { Gyro.gyroraw() }
+
+        fn gyro<F>(&mut self) -> Result<Vector3<RadiansPerSecond<F>>, ()>
+            where F: Clone + Copy + From<f32> + From<i16> +
+                Add<F, Output=F> + Sub<F, Output=F> + Mul<F, Output=F> + Div<F, Output=F>
+        {
+            Gyro.gyro()
+        }
+
+        fn gyro_mdps(&mut self) -> Result<Vector3<MilliDegreesPerSecond>, ()> { Gyro.gyro_mdps() }
+    }
+
+    impl Thermometer for GyroTemp {
+        type Error = ();
+        type Output = i8;
+
+        fn tempraw(&mut self) -> Result<i8, ()> { Ok(0) }
+
+        fn temp<F>(&mut self) -> Result<Celsius<F>, ()>
+            where F: Clone + Copy + From<f32> + From<i8> +
+                Add<F, Output=F> + Sub<F, Output=F> + Mul<F, Output=F> + Div<F, Output=F>
+        {
+            Ok( Celsius(F::from(25.0)) )
+        }
+
+        fn temp_mc(&mut self) -> Result<MilliCelsius, ()> { Ok( MilliCelsius(25_000) ) }
+    }
+
+    impl Clock for Counter {
+        type Instant = u32;
+
+        fn now(&mut self) -> u32 {
+            self.0 += 1;
+            self.0
+        }
+    }
+
+    #[test]
+    fn six_dof() {
+        let mut imu = Composite::new(Accel, Gyro, Counter(0));
+
+        let sample: ImuSample<f32, u32> = imu.sample().unwrap();
+
+        assert_eq!(sample.timestamp, 1);
+        assert_eq!(sample.accel.z, MetersPerSecondSquared(9.8));
+        assert_eq!(sample.gyro.y, RadiansPerSecond(1.0));
+        assert_eq!(sample.mag, None);
+        assert_eq!(sample.temp, None);
+    }
+
+    #[test]
+    fn nine_dof() {
+        let mut imu = Composite::new(Accel, GyroTemp, Counter(0))
+            .with_magnetometer()
+            .with_thermometer();
+
+        let _: ImuSample<f32, u32> = imu.sample().unwrap();
+        let sample: ImuSample<f32, u32> = imu.sample().unwrap();
+
+        assert_eq!(sample.timestamp, 2);
+        assert_eq!(sample.mag.map(|m| m.x), Some(MicroTesla(10.0)));
+        assert_eq!(sample.temp, Some(Celsius(25.0)));
+    }
+}
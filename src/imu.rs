@@ -0,0 +1,49 @@
+//! Module for inertial measurement units and combined peripherals.
+
+
+use crate::units::{ Celsius, MetersPerSecondSquared, MicroTesla, RadiansPerSecond, Vector3 };
+
+
+
+/// Source of the timestamps of the IMU samples.
+pub trait Clock {
+    /// Point in time returned by the clock.
+    type Instant: Clone + Copy;
+
+    /// Returns the current instant.
+    fn now(&mut self) -> Self::Instant;
+}
+
+
+
+/// Sample of all the sensors of an IMU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuSample<F, T> {
+    /// Instant at which the sample was taken.
+    pub timestamp: T,
+
+    /// Acceleration.
+    pub accel: Vector3<MetersPerSecondSquared<F>>,
+
+    /// Angular rate.
+    pub gyro: Vector3<RadiansPerSecond<F>>,
+
+    /// Magnetic field. `None` for 6-DoF units.
+    pub mag: Option<Vector3<MicroTesla<F>>>,
+
+    /// Die temperature. `None` for units without a thermometer.
+    pub temp: Option<Celsius<F>>,
+}
+
+
+
+/// Common trait for all 6-DoF and 9-DoF inertial measurement units.
+/// The `F` type is the `f32` wrapper used by the normalization of the
+/// individual sensors.
+pub trait Imu<F> {
+    type Error;
+    type Instant;
+
+    /// Reads all the sensors and returns a single timestamped sample.
+    fn sample(&mut self) -> Result<ImuSample<F, Self::Instant>, Self::Error>;
+}
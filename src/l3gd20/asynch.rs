@@ -0,0 +1,274 @@
+//! Async L3GD20 driver.
+//! Shares the configuration, register map and conversions with the blocking
+//! `L3gd20` driver.
+
+
+use super::{ gyro, Config, Error, L3gd20 };
+use super::interface::AsyncInterface;
+
+use crate::{ AsyncGyroscope, AsyncThermometer, Saturation };
+use crate::units::{ Celsius, MilliCelsius, MilliDegreesPerSecond, RadiansPerSecond, Vector3 };
+
+use core::ops::*;
+
+
+
+/// Async L3GD20 driver.
+/// Covers the configuration, setters and the sensor traits. The FIFO,
+/// threshold interrupts, reference, self-test, reboot and non-blocking
+/// `try_*` reads are only available in the blocking `L3gd20` driver.
+pub struct L3gd20Async<I>(L3gd20<I>);
+
+impl<I: AsyncInterface> L3gd20Async<I> {
+
+    /// Creates a new driver and configures the device.
+    /// Fails with `Error::WrongDevice` if the device does not identify as
+    /// an L3GD20 or L3GD20H.
+    pub async fn create(interface: I, cfg: Config) -> Result<Self, Error<I::Error>> {
+        // Create the device.
+        let mut device = L3gd20Async( L3gd20::build(interface, cfg) );
+
+        // Check the identity of the device.
+        device.identify().await?;
+
+        // Configure the device.
+        match device.configure().await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok(device)
+    }
+
+    /// Creates a new driver for an already configured device.
+    /// The configuration is read back from the device instead of written.
+    /// Fails with `Error::WrongDevice` if the device does not identify as
+    /// an L3GD20 or L3GD20H.
+    pub async fn attach(interface: I) -> Result<Self, Error<I::Error>> {
+        // Create the device with a placeholder configuration.
+        let mut device = L3gd20Async( L3gd20::build(interface, Config::default()) );
+
+        // Check the identity of the device.
+        device.identify().await?;
+
+        // Adopt the configuration of the device.
+        device.0.cfg = device.read_config().await?;
+        device.0.gyro = device.0.cfg.params();
+
+        Ok(device)
+    }
+
+    /// Reads the current configuration from the device registers.
+    pub async fn read_config(&mut self) -> Result<Config, Error<I::Error>> {
+        let mut ctrl = [0u8; 5];
+        let mut fifo = [0u8; 1];
+
+        match self.rd(gyro::Register::Ctrl1, &mut ctrl).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.rd(gyro::Register::FIFOCtrl, &mut fifo).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( Config::from_registers(ctrl, fifo[0]) )
+    }
+
+    /// Reads the WhoAmI register and checks it against the known identities.
+    async fn identify(&mut self) -> Result<(), Error<I::Error>> {
+        let mut data = [0u8; 1];
+
+        match self.rd(gyro::Register::WhoAmI, &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        if !gyro::IDENTITY.contains(&data[0]) {
+            return Err( Error::WrongDevice { found: data[0] } )
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last applied configuration to the device.
+    async fn configure(&mut self) -> Result<(), I::Error> {
+        // Configure the gyroscope module and the FIFO.
+        for (reg, value) in self.0.cfg.registers() {
+            self.wr(reg, value).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps the device.
+    /// To wake it up, the user must select again the output data rate,
+    /// call `wake` or reset the device.
+    pub async fn sleep(&mut self) -> Result<(), I::Error> {
+        self.wr(gyro::Register::Ctrl1, 0b00001000).await
+    }
+
+    /// Wakes the device up from sleep with the last applied configuration.
+    pub async fn wake(&mut self) -> Result<(), I::Error> {
+        self.wr(gyro::Register::Ctrl1, self.0.cfg.ctrl1).await
+    }
+
+    /// Applies a new configuration to the device.
+    /// Only the registers that differ from the last applied configuration
+    /// are written.
+    pub async fn apply(&mut self, cfg: Config) -> Result<(), I::Error> {
+        let new = cfg.registers();
+
+        for (i, (reg, old)) in self.0.cfg.registers().into_iter().enumerate() {
+            if old == new[i].1 { continue }
+
+            self.wr(reg, new[i].1).await?;
+
+            // Keep the cache in sync with the registers already written.
+            self.0.cfg.set(i, new[i].1);
+            self.0.gyro = self.0.cfg.params();
+        }
+
+        Ok(())
+    }
+
+    /// Changes the gyroscope scale.
+    pub async fn set_scale(&mut self, scale: gyro::Scale) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.scale(scale) ).await
+    }
+
+    /// Changes the gyroscope output data rate.
+    pub async fn set_datarate(&mut self, rate: gyro::DataRate) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.datarate(rate) ).await
+    }
+
+    /// Changes the gyroscope filter bandwidth.
+    pub async fn set_bandwidth(&mut self, bandwidth: gyro::Bandwidth) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.bandwidth(bandwidth) ).await
+    }
+
+    /// One-point calibration of the temperature sensor.
+    /// Reads the current output and stores the offset that makes it equal
+    /// to the given known temperature in ºC.
+    pub async fn calibrate_temperature(&mut self, known: f32) -> Result<(), Error<I::Error>> {
+        let raw = self.tempraw().await?;
+
+        self.0.calibrate(raw, known);
+
+        Ok(())
+    }
+
+    /// Internal write function.
+    #[inline(always)]
+    async fn wr(&mut self, reg: gyro::Register, value: u8) -> Result<(), I::Error> {
+        self.0.interface.write(reg as u8, value).await
+    }
+
+    /// Internal read function.
+    #[inline(always)]
+    async fn rd(&mut self, reg: gyro::Register, buffer: &mut [u8]) -> Result<(), I::Error> {
+        self.0.interface.read(reg as u8, buffer).await
+    }
+}
+
+impl<I> L3gd20Async<I> {
+    /// Returns the last configuration applied to the device.
+    pub fn config(&self) -> Config {
+        self.0.config()
+    }
+
+    /// Sets the policy applied to saturated axis in the normalized output.
+    pub fn set_saturation(&mut self, policy: Saturation) {
+        self.0.set_saturation(policy)
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized sample.
+    pub fn saturated(&self) -> [bool; 3] {
+        self.0.saturated()
+    }
+
+    /// Sets the temperature in ºC that corresponds to a raw output of 0.
+    pub fn set_temperature_offset(&mut self, offset: f32) {
+        self.0.set_temperature_offset(offset)
+    }
+
+    /// Returns the temperature offset in ºC.
+    pub fn temperature_offset(&self) -> f32 {
+        self.0.temperature_offset()
+    }
+}
+
+
+
+impl<I: AsyncInterface> AsyncGyroscope for L3gd20Async<I> {
+    type Error = Error<I::Error>;
+    type Output = i16;
+
+    async fn gyroraw(&mut self) -> Result<[i16; 3], Error<I::Error>> {
+        // Create the input buffer.
+        let mut data = [0u8; 6];
+
+        // Read in the output data.
+        match self.rd(gyro::Register::OutXL, &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( gyro::decode(&data) )
+    }
+
+    async fn gyro<F>(&mut self) -> Result<Vector3<RadiansPerSecond<F>>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.gyroraw().await?;
+
+        self.0.normalize(raw)
+    }
+
+    async fn gyro_mdps(&mut self) -> Result<Vector3<MilliDegreesPerSecond>, Error<I::Error>> {
+        let raw = self.gyroraw().await?;
+
+        self.0.normalize_fixed(raw)
+    }
+}
+
+
+
+impl<I: AsyncInterface> AsyncThermometer for L3gd20Async<I> {
+    type Error = Error<I::Error>;
+    type Output = i8;
+
+    async fn tempraw(&mut self) -> Result<i8, Error<I::Error>> {
+        // Create the input buffer.
+        let mut data = [0u8; 1];
+
+        // Read in the output data.
+        match self.rd(gyro::Register::TempOut, &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( data[0] as i8 )
+    }
+
+    async fn temp<F>(&mut self) -> Result<Celsius<F>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.tempraw().await?;
+
+        Ok( self.0.celsius(raw) )
+    }
+
+    async fn temp_mc(&mut self) -> Result<MilliCelsius, Error<I::Error>> {
+        let raw = self.tempraw().await?;
+
+        Ok( self.0.milli_celsius(raw) )
+    }
+}
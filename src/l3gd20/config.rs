@@ -20,6 +20,9 @@ pub struct Config {
 
     /// Ctrl5 register.
     pub(super) ctrl5: u8,
+
+    /// FIFO Control register.
+    pub(super) fifoctrl: u8,
 }
 
 
@@ -64,11 +67,124 @@ impl Config {
         self
     }
 
+    /// Selects the 3-wire SPI interface instead of the 4-wire one.
+    #[inline(always)]
+    pub const fn spi3wire(mut self, s: bool) -> Self {
+        if s { self.ctrl4 |= 1 << 0 }
+        else { self.ctrl4 &= !(1 << 0) }
+
+        self
+    }
+
+    /// Sets the high pass filter mode and cutoff frequency.
+    /// The cutoff frequency depends on the selected data rate.
+    #[inline(always)]
+    pub const fn highpass(mut self, mode: gyro::HighPassMode, cutoff: gyro::HighPassCutoff) -> Self {
+        self.ctrl2 = ((mode as u8) << 4) | (cutoff as u8);
+        self
+    }
+
+    /// Enables / Disables the high pass filter.
+    /// When disabled, the filter is bypassed in both the output and the
+    /// interrupt paths.
+    #[inline(always)]
+    pub const fn highpass_enable(mut self, s: bool) -> Self {
+        if s { self.ctrl5 |= 1 << 4 }
+        else { self.ctrl5 &= !(1 << 4) }
+
+        self
+    }
+
+    /// Sets the filter path of the output data registers and the FIFO.
+    #[inline(always)]
+    pub const fn output(mut self, path: gyro::OutputSelect) -> Self {
+        self.ctrl5 &= !0x3;
+        self.ctrl5 |= path as u8;
+
+        self
+    }
+
+    /// Sets the filter path of the interrupt generator.
+    #[inline(always)]
+    pub const fn interrupt_path(mut self, path: gyro::OutputSelect) -> Self {
+        self.ctrl5 &= !(0x3 << 2);
+        self.ctrl5 |= (path as u8) << 2;
+
+        self
+    }
+
+    /// Sets the FIFO mode and the watermark level.
+    /// The watermark is a 5 bit value, higher bits are ignored.
+    /// The FIFO is enabled in CTRL5 unless the `Bypass` mode is selected.
+    #[inline(always)]
+    pub const fn fifo(mut self, mode: gyro::FIFOMode, watermark: u8) -> Self {
+        self.fifoctrl = ((mode as u8) << 5) | (watermark & 0x1F);
+
+        match mode {
+            gyro::FIFOMode::Bypass => self.ctrl5 &= !(1 << 6),
+            _ => self.ctrl5 |= 1 << 6,
+        }
+
+        self
+    }
+
+    /// Builds the configuration read back from the CTRL1-5 and FIFO
+    /// Control registers.
+    pub(super) fn from_registers(ctrl: [u8; 5], fifoctrl: u8) -> Self {
+        Config {
+            ctrl1: ctrl[0],
+            ctrl2: ctrl[1],
+            ctrl3: ctrl[2],
+            ctrl4: ctrl[3],
+            ctrl5: ctrl[4],
+
+            fifoctrl,
+        }
+    }
+
+    /// Returns the registers and their values in the order they are
+    /// written to the device.
+    pub(super) fn registers(&self) -> [(gyro::Register, u8); 6] {
+        [
+            (gyro::Register::Ctrl1,    self.ctrl1   ),
+            (gyro::Register::Ctrl2,    self.ctrl2   ),
+            (gyro::Register::Ctrl3,    self.ctrl3   ),
+            (gyro::Register::Ctrl4,    self.ctrl4   ),
+            (gyro::Register::Ctrl5,    self.ctrl5   ),
+            (gyro::Register::FIFOCtrl, self.fifoctrl),
+        ]
+    }
+
+    /// Sets the value of the register at the given index of `registers`.
+    pub(super) fn set(&mut self, index: usize, value: u8) {
+        match index {
+            0 => self.ctrl1 = value,
+            1 => self.ctrl2 = value,
+            2 => self.ctrl3 = value,
+            3 => self.ctrl4 = value,
+            4 => self.ctrl5 = value,
+            _ => self.fifoctrl = value,
+        }
+    }
+
     /// Returns the parameters.
     pub fn params(&self) -> gyro::Range {
         // Get the gyroscope range.
         gyro::Range::from( (self.ctrl4 >> 4) & 0x3 )
     }
+
+    /// Returns the high pass filter cutoff frequency in Hz at the configured
+    /// data rate.
+    /// Returns `None` if the cutoff selection is not a documented value
+    /// (e.g. a configuration read back from the device).
+    pub fn highpass_frequency(&self) -> Option<f32> {
+        let rate = gyro::DataRate::from( (self.ctrl1 >> 6) & 0x3 );
+
+        match self.ctrl2 & 0xF {
+            0b1010..=0b1111 => None,
+            hpcf => Some( gyro::HighPassCutoff::from(hpcf).frequency(rate) ),
+        }
+    }
 }
 
 impl const Default for Config {
@@ -78,7 +194,27 @@ impl const Default for Config {
             ctrl2: 0b00000000,
             ctrl3: 0b00000000,
             ctrl4: 0b10000000,
-            ctrl5: 0b01000011,
+            ctrl5: 0b01000000 | gyro::OutputSelect::LowPassFilter as u8,
+
+            fifoctrl: 0b00000000,
         }
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highpass_frequency() {
+        // 380 Hz data rate, HPCF = 0010.
+        let cfg = Config::from_registers([0b10001111, 0b0010, 0, 0, 0], 0);
+        assert_eq!(cfg.highpass_frequency(), Some(7.2));
+
+        // HPCF = 1010 is not documented.
+        let cfg = Config::from_registers([0b10001111, 0b1010, 0, 0, 0], 0);
+        assert_eq!(cfg.highpass_frequency(), None);
+    }
+}
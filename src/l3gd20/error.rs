@@ -8,6 +8,21 @@ pub enum Error<E> {
     RangeOverflowY,
     RangeOverflowZ,
 
+    /// The device on the bus did not identify as an L3GD20.
+    /// Contains the value read from the WhoAmI register.
+    WrongDevice { found: u8 },
+
     /// An error ocurred in the underlying bus subsystem.
     BusError(E)
 }
+
+impl<E> Error<E> {
+    /// Returns the range overflow error of the given axis index.
+    pub(super) fn overflow(axis: usize) -> Self {
+        match axis {
+            0 => Error::RangeOverflowX,
+            1 => Error::RangeOverflowY,
+            _ => Error::RangeOverflowZ,
+        }
+    }
+}
@@ -4,6 +4,24 @@
 #![allow(dead_code)]
 
 
+
+/// Time in us to wait after rebooting the memory content.
+pub(crate) const BOOT_TIME: u32 = 10_000;
+
+/// Time in us to wait for the output to settle after toggling the self-test.
+pub(crate) const SELFTEST_SETTLE: u32 = 50_000;
+
+/// Number of samples averaged in each phase of the self-test.
+pub(crate) const SELFTEST_SAMPLES: i32 = 5;
+
+/// Default temperature in ºC that corresponds to a raw output of 0.
+pub(crate) const TEMP_OFFSET: f32 = 25.0;
+
+/// Accepted contents of the WhoAmI register.
+/// 0xD4 identifies the L3GD20 and 0xD7 the L3GD20H.
+pub(crate) const IDENTITY: [u8; 2] = [0xD4, 0xD7];
+
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Axis {
@@ -17,6 +35,8 @@ pub enum Axis {
     ZAxis = 0b100,
 }
 
+// The axis masks are combined with a bitwise OR.
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<Axis> for Axis {
     type Output = u8;
 
@@ -26,6 +46,7 @@ impl const core::ops::Add<Axis> for Axis {
     }
 }
 
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<u8> for Axis {
     type Output = u8;
 
@@ -35,6 +56,7 @@ impl const core::ops::Add<u8> for Axis {
     }
 }
 
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<Axis> for u8 {
     type Output = u8;
 
@@ -79,6 +101,32 @@ pub enum DataRate {
     Hz760  = 0b11,
 }
 
+impl DataRate {
+    /// Returns the data rate in Hz.
+    #[inline]
+    pub fn hz(&self) -> u32 {
+        match *self {
+            DataRate::Hz95  =>  95,
+            DataRate::Hz190 => 190,
+            DataRate::Hz380 => 380,
+            DataRate::Hz760 => 760,
+        }
+    }
+}
+
+impl core::convert::From<u8> for DataRate {
+    fn from(s: u8) -> DataRate {
+        match s {
+            0b00 => DataRate::Hz95,
+            0b01 => DataRate::Hz190,
+            0b10 => DataRate::Hz380,
+            0b11 => DataRate::Hz760,
+
+            _ => panic!(),
+        }
+    }
+}
+
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -102,6 +150,130 @@ pub enum FIFOMode {
 
 
 
+/// Number of samples the FIFO can hold.
+pub const FIFO_DEPTH: usize = 32;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FIFOStatus {
+    /// Number of unread samples stored in the FIFO.
+    pub level: usize,
+
+    /// The FIFO level is equal or higher than the watermark level.
+    pub watermark: bool,
+
+    /// The FIFO is full and the oldest samples are being overwritten.
+    pub overrun: bool,
+}
+
+impl core::convert::From<u8> for FIFOStatus {
+    fn from(s: u8) -> FIFOStatus {
+        let watermark = (s & (1 << 7)) != 0;
+        let overrun   = (s & (1 << 6)) != 0;
+        let empty     = (s & (1 << 5)) != 0;
+
+        // The stored data level only has 5 bits, a full FIFO is signaled
+        // through the overrun flag.
+        let level = if empty { 0 }
+            else if overrun { FIFO_DEPTH }
+            else { (s & 0x1F) as usize };
+
+        FIFOStatus { level, watermark, overrun }
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HighPassMode {
+    /// Normal mode. The filter is reset by reading the Reference register.
+    NormalReset = 0b00,
+
+    /// Reference signal for filtering.
+    /// The output is the difference between the input and the Reference register.
+    Reference = 0b01,
+
+    /// Normal mode.
+    Normal = 0b10,
+
+    /// Autoreset on interrupt event.
+    AutoReset = 0b11,
+}
+
+
+
+/// High pass filter cutoff frequencies in Hz.
+/// Each data rate uses a window of 10 consecutive values of this table.
+const HPCUTOFF: [f32; 13] = [
+    51.4, 27.0, 13.5, 7.2, 3.5, 1.8, 0.9, 0.45, 0.18, 0.09, 0.045, 0.018, 0.009,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HighPassCutoff {
+    /// Highest cutoff frequency.
+    /// 7.2 Hz at 95 Hz, 13.5 Hz at 190 Hz, 27 Hz at 380 Hz, 51.4 Hz at 760 Hz.
+    Hpcf0 = 0b0000,
+    Hpcf1 = 0b0001,
+    Hpcf2 = 0b0010,
+    Hpcf3 = 0b0011,
+    Hpcf4 = 0b0100,
+    Hpcf5 = 0b0101,
+    Hpcf6 = 0b0110,
+    Hpcf7 = 0b0111,
+    Hpcf8 = 0b1000,
+
+    /// Lowest cutoff frequency.
+    /// 0.009 Hz at 95 Hz, 0.018 Hz at 190 Hz, 0.045 Hz at 380 Hz, 0.09 Hz at 760 Hz.
+    Hpcf9 = 0b1001,
+}
+
+impl HighPassCutoff {
+    /// Returns the cutoff frequency in Hz at the given data rate.
+    pub fn frequency(&self, rate: DataRate) -> f32 {
+        HPCUTOFF[(*self as usize) + 3 - (rate as usize)]
+    }
+
+    /// Returns the cutoff selection closest to the given frequency in Hz at
+    /// the given data rate.
+    pub fn closest(rate: DataRate, hz: f32) -> HighPassCutoff {
+        let mut best = (HighPassCutoff::Hpcf0, f32::MAX);
+
+        for i in 0..10 {
+            let cutoff = HighPassCutoff::from(i);
+            let diff = cutoff.frequency(rate) - hz;
+            let diff = if diff < 0.0 { -diff } else { diff };
+
+            if diff < best.1 { best = (cutoff, diff) }
+        }
+
+        best.0
+    }
+}
+
+impl core::convert::From<u8> for HighPassCutoff {
+    fn from(s: u8) -> HighPassCutoff {
+        match s {
+            0b0000 => HighPassCutoff::Hpcf0,
+            0b0001 => HighPassCutoff::Hpcf1,
+            0b0010 => HighPassCutoff::Hpcf2,
+            0b0011 => HighPassCutoff::Hpcf3,
+            0b0100 => HighPassCutoff::Hpcf4,
+            0b0101 => HighPassCutoff::Hpcf5,
+            0b0110 => HighPassCutoff::Hpcf6,
+            0b0111 => HighPassCutoff::Hpcf7,
+            0b1000 => HighPassCutoff::Hpcf8,
+            0b1001 => HighPassCutoff::Hpcf9,
+
+            _ => panic!(),
+        }
+    }
+}
+
+
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum OutputSelect {
@@ -133,13 +305,22 @@ pub enum Range {
 pub type Scale = Range;
 
 impl Range {
-    /// Returns the parameters to calculate the real acceleration.
+    /// Returns the parameters to calculate the real angular rate.
+    /// The sensitivity in dps / LSB is derived from `mdps`.
     #[inline]
     pub(super) fn params(&self) -> f32 {
+        let (num, den) = self.mdps();
+
+        num as f32 / (den as f32 * 1000.0)
+    }
+
+    /// Returns the sensitivity in mdps / LSB as an exact fraction.
+    #[inline]
+    pub(super) fn mdps(&self) -> (i32, i32) {
         match *self {
-            Range::Dps250  => 0.00875,
-            Range::Dps500  => 0.01750,
-            Range::Dps2000 => 0.07000,
+            Range::Dps250  => (35, 4),
+            Range::Dps500  => (35, 2),
+            Range::Dps2000 => (70, 1),
         }
     }
 }
@@ -224,3 +405,64 @@ pub(crate) enum Register {
     /// Interrupt Duration register.
     IntDur = 0x38,
 }
+
+
+
+/// Decodes a little endian X, Y and Z sample.
+#[inline(always)]
+pub(super) fn decode(data: &[u8]) -> [i16; 3] {
+    [
+        i16::from_le_bytes([data[0], data[1]]),
+        i16::from_le_bytes([data[2], data[3]]),
+        i16::from_le_bytes([data[4], data[5]]),
+    ]
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_status() {
+        assert_eq!(FIFOStatus::from(0b0010_0000), FIFOStatus { level:  0, watermark: false, overrun: false });
+        assert_eq!(FIFOStatus::from(0b0000_0111), FIFOStatus { level:  7, watermark: false, overrun: false });
+        assert_eq!(FIFOStatus::from(0b1001_1111), FIFOStatus { level: 31, watermark: true,  overrun: false });
+
+        // A full FIFO reads 0 stored samples with the overrun flag set.
+        assert_eq!(FIFOStatus::from(0b1100_0000), FIFOStatus { level: FIFO_DEPTH, watermark: true, overrun: true });
+    }
+
+    #[test]
+    fn range_sensitivity() {
+        // Datasheet sensitivity in mdps / digit.
+        assert_eq!(Range::Dps250.mdps(),  (35, 4));
+        assert_eq!(Range::Dps500.mdps(),  (35, 2));
+        assert_eq!(Range::Dps2000.mdps(), (70, 1));
+
+        assert_eq!(Range::Dps250.params(),  0.00875);
+        assert_eq!(Range::Dps500.params(),  0.0175);
+        assert_eq!(Range::Dps2000.params(), 0.07);
+    }
+
+    #[test]
+    fn highpass_cutoff_frequency() {
+        assert_eq!(HighPassCutoff::Hpcf0.frequency(DataRate::Hz95),  7.2);
+        assert_eq!(HighPassCutoff::Hpcf0.frequency(DataRate::Hz190), 13.5);
+        assert_eq!(HighPassCutoff::Hpcf0.frequency(DataRate::Hz380), 27.0);
+        assert_eq!(HighPassCutoff::Hpcf0.frequency(DataRate::Hz760), 51.4);
+
+        assert_eq!(HighPassCutoff::Hpcf9.frequency(DataRate::Hz95),  0.009);
+        assert_eq!(HighPassCutoff::Hpcf9.frequency(DataRate::Hz190), 0.018);
+        assert_eq!(HighPassCutoff::Hpcf9.frequency(DataRate::Hz380), 0.045);
+        assert_eq!(HighPassCutoff::Hpcf9.frequency(DataRate::Hz760), 0.09);
+    }
+
+    #[test]
+    fn highpass_cutoff_closest() {
+        assert_eq!(HighPassCutoff::closest(DataRate::Hz95, 1.0), HighPassCutoff::Hpcf3);
+        assert_eq!(HighPassCutoff::closest(DataRate::Hz760, 100.0), HighPassCutoff::Hpcf0);
+        assert_eq!(HighPassCutoff::closest(DataRate::Hz760, 0.0), HighPassCutoff::Hpcf9);
+    }
+}
@@ -0,0 +1,343 @@
+//! Register access interfaces for the L3GD20 device.
+
+
+use embedded_hal::digital::OutputPin;
+
+use embedded_hal::i2c::{ I2c, SevenBitAddress };
+
+use embedded_hal::spi::SpiBus;
+
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+
+
+/// Common trait for the buses that give access to the device registers.
+pub trait Interface {
+    type Error;
+
+    /// Writes a value to the given register.
+    fn write(&mut self, reg: u8, value: u8) -> Result<(), Self::Error>;
+
+    /// Reads the buffer from the given register onwards.
+    /// Reads of more than one byte use register address auto-increment.
+    fn read(&mut self, reg: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Async version of the `Interface` trait.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncInterface {
+    type Error;
+
+    /// Writes a value to the given register.
+    async fn write(&mut self, reg: u8, value: u8) -> Result<(), Self::Error>;
+
+    /// Reads the buffer from the given register onwards.
+    /// Reads of more than one byte use register address auto-increment.
+    async fn read(&mut self, reg: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+
+
+/// I2C interface.
+pub struct I2cInterface<I> {
+    /// I2C bus.
+    i2c: I,
+
+    /// Address of the device.
+    /// L3GD20 has a selectable address bit.
+    addr: SevenBitAddress,
+}
+
+impl<I> I2cInterface<I> {
+    /// Creates a new I2C interface.
+    /// As the address of this sensor is hardware configurable, the lowbit flag
+    /// indicates that the 0x6B address is used (SDO high), rather than the 0x6A.
+    pub fn new(i2c: I, lowbit: bool) -> Self {
+        let addr = if lowbit { 0x6B } else { 0x6A };
+
+        I2cInterface { i2c, addr }
+    }
+
+    /// Releases the I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+impl<E, I: I2c<SevenBitAddress, Error=E>> Interface for I2cInterface<I> {
+    type Error = E;
+
+    fn write(&mut self, reg: u8, value: u8) -> Result<(), E> {
+        self.i2c.write(self.addr, &[reg, value])
+    }
+
+    fn read(&mut self, reg: u8, buffer: &mut [u8]) -> Result<(), E> {
+        // The MSB of the sub address enables auto-increment.
+        let reg = if buffer.len() > 1 { reg | (1 << 7) } else { reg };
+
+        self.i2c.write_read(self.addr, &[reg], buffer)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: AsyncI2c> AsyncInterface for I2cInterface<I> {
+    type Error = I::Error;
+
+    async fn write(&mut self, reg: u8, value: u8) -> Result<(), I::Error> {
+        self.i2c.write(self.addr, &[reg, value]).await
+    }
+
+    async fn read(&mut self, reg: u8, buffer: &mut [u8]) -> Result<(), I::Error> {
+        // The MSB of the sub address enables auto-increment.
+        let reg = if buffer.len() > 1 { reg | (1 << 7) } else { reg };
+
+        self.i2c.write_read(self.addr, &[reg], buffer).await
+    }
+}
+
+
+
+/// Errors of the SPI interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiError<S, P> {
+    /// An error ocurred in the SPI bus.
+    Bus(S),
+
+    /// An error ocurred while driving the chip select pin.
+    ChipSelect(P),
+}
+
+/// SPI interface.
+/// Works in both 3-wire and 4-wire mode. The 3-wire mode must also be
+/// selected in the device configuration.
+pub struct SpiInterface<S, P> {
+    /// SPI bus.
+    spi: S,
+
+    /// Chip select pin (active low).
+    cs: P,
+}
+
+impl<S, P> SpiInterface<S, P> {
+    /// Creates a new SPI interface.
+    pub fn new(spi: S, cs: P) -> Self {
+        SpiInterface { spi, cs }
+    }
+
+    /// Releases the SPI bus and the chip select pin.
+    pub fn release(self) -> (S, P) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<E, S: SpiBus<u8, Error=E>, P: OutputPin> SpiInterface<S, P> {
+    /// Runs a transaction with the chip select asserted.
+    /// The chip select is released even if the transaction fails.
+    fn transaction<F>(&mut self, f: F) -> Result<(), SpiError<E, P::Error>>
+        where F: FnOnce(&mut S) -> Result<(), E>
+    {
+        if let Err(e) = self.cs.set_low() { return Err( SpiError::ChipSelect(e) ) }
+
+        // Wait for the bus to finish before releasing the chip select.
+        let result = f(&mut self.spi).and_then(|_| self.spi.flush());
+
+        if let Err(e) = self.cs.set_high() { return Err( SpiError::ChipSelect(e) ) }
+
+        match result {
+            Err(e) => Err( SpiError::Bus(e) ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<E, S: SpiBus<u8, Error=E>, P: OutputPin> Interface for SpiInterface<S, P> {
+    type Error = SpiError<E, P::Error>;
+
+    fn write(&mut self, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.transaction(|spi| spi.write(&[reg & 0x3F, value]))
+    }
+
+    fn read(&mut self, reg: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        // Bit 7 selects a read and bit 6 enables auto-increment.
+        let reg = if buffer.len() > 1 { reg | (1 << 7) | (1 << 6) } else { reg | (1 << 7) };
+
+        self.transaction(|spi| {
+            spi.write(&[reg])?;
+            spi.read(buffer)?;
+
+            Ok(())
+        })
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::convert::Infallible;
+
+    use embedded_hal::i2c::Operation;
+
+    /// I2C bus that records the last transaction.
+    struct I2cBus {
+        addr: u8,
+        written: [u8; 2],
+        len: usize,
+    }
+
+    impl embedded_hal::i2c::ErrorType for I2cBus {
+        type Error = Infallible;
+    }
+
+    impl I2c for I2cBus {
+        fn transaction(&mut self, addr: u8, operations: &mut [Operation<'_>]) -> Result<(), Infallible> {
+            self.addr = addr;
+
+            for op in operations {
+                match op {
+                    Operation::Write(bytes) => {
+                        self.len = bytes.len();
+                        self.written[..bytes.len()].copy_from_slice(bytes);
+                    },
+
+                    Operation::Read(buffer) => buffer.fill(0xA5),
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// SPI bus that records the written bytes and reads back a pattern.
+    struct Spi {
+        written: [u8; 4],
+        len: usize,
+    }
+
+    impl embedded_hal::spi::ErrorType for Spi {
+        type Error = Infallible;
+    }
+
+    impl SpiBus<u8> for Spi {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+            for (i, w) in words.iter_mut().enumerate() { *w = i as u8 + 1 }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+            self.written[self.len..self.len + words.len()].copy_from_slice(words);
+            self.len += words.len();
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Infallible> {
+            self.write(write)?;
+            self.read(read)
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+            self.read(words)
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    /// Chip select that counts the transactions.
+    struct Cs {
+        low: bool,
+        transactions: usize,
+    }
+
+    impl embedded_hal::digital::ErrorType for Cs {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Cs {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.low = true;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            if self.low { self.transactions += 1 }
+            self.low = false;
+            Ok(())
+        }
+    }
+
+    fn interface() -> SpiInterface<Spi, Cs> {
+        SpiInterface::new(Spi { written: [0; 4], len: 0 }, Cs { low: false, transactions: 0 })
+    }
+
+    #[test]
+    fn i2c_address() {
+        let mut i2c = I2cInterface::new(I2cBus { addr: 0, written: [0; 2], len: 0 }, false);
+        i2c.write(0x20, 0x0F).unwrap();
+        assert_eq!(i2c.release().addr, 0x6A);
+
+        let mut i2c = I2cInterface::new(I2cBus { addr: 0, written: [0; 2], len: 0 }, true);
+        i2c.write(0x20, 0x0F).unwrap();
+        assert_eq!(i2c.release().addr, 0x6B);
+    }
+
+    #[test]
+    fn i2c_framing() {
+        let mut i2c = I2cInterface::new(I2cBus { addr: 0, written: [0; 2], len: 0 }, false);
+
+        // Single byte reads do not auto-increment.
+        let mut buffer = [0u8; 1];
+        i2c.read(0x0F, &mut buffer).unwrap();
+        assert_eq!(i2c.i2c.written[..i2c.i2c.len], [0x0F]);
+        assert_eq!(buffer, [0xA5]);
+
+        // Burst reads set the MSB of the sub address.
+        let mut buffer = [0u8; 6];
+        i2c.read(0x28, &mut buffer).unwrap();
+        assert_eq!(i2c.i2c.written[..i2c.i2c.len], [0xA8]);
+
+        i2c.write(0x20, 0x0F).unwrap();
+        assert_eq!(i2c.i2c.written[..i2c.i2c.len], [0x20, 0x0F]);
+    }
+
+    #[test]
+    fn spi_write_framing() {
+        let mut spi = interface();
+
+        // The read and auto-increment bits are cleared on writes.
+        spi.write(0xE0, 0x0F).unwrap();
+
+        let (bus, cs) = spi.release();
+        assert_eq!(bus.written[..bus.len], [0x20, 0x0F]);
+        assert_eq!(cs.transactions, 1);
+        assert!( !cs.low );
+    }
+
+    #[test]
+    fn spi_read_framing() {
+        // Single byte reads set the read bit only.
+        let mut spi = interface();
+        let mut buffer = [0u8; 1];
+        spi.read(0x0F, &mut buffer).unwrap();
+
+        let (bus, cs) = spi.release();
+        assert_eq!(bus.written[..bus.len], [0x8F]);
+        assert_eq!(buffer, [1]);
+        assert_eq!(cs.transactions, 1);
+
+        // Burst reads set the read and auto-increment bits.
+        let mut spi = interface();
+        let mut buffer = [0u8; 6];
+        spi.read(0x28, &mut buffer).unwrap();
+
+        let (bus, cs) = spi.release();
+        assert_eq!(bus.written[..bus.len], [0xE8]);
+        assert_eq!(buffer, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(cs.transactions, 1);
+    }
+}
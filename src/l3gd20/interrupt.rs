@@ -0,0 +1,210 @@
+//! Threshold interrupt configuration for the L3GD20 device.
+
+
+use super::gyro;
+
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Interrupt {
+    /// Interrupt Configuration register.
+    pub(super) cfg: u8,
+
+    /// X, Y and Z axis thresholds in dps.
+    pub(super) threshold: [f32; 3],
+
+    /// Interrupt Duration register.
+    pub(super) duration: u8,
+}
+
+impl Interrupt {
+    /// Static initializer.
+    /// All events are disabled and the events are combined with an OR.
+    pub const fn new() -> Self {
+        Interrupt {
+            cfg: 0b00000000,
+            threshold: [0.0; 3],
+            duration: 0b00000000,
+        }
+    }
+
+    /// Enables the interrupt on the angular rate of the given axis rising
+    /// above their threshold.
+    #[inline(always)]
+    pub const fn high(mut self, axis: u8) -> Self {
+        if (axis & gyro::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 1 }
+        if (axis & gyro::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 3 }
+        if (axis & gyro::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 5 }
+
+        self
+    }
+
+    /// Enables the interrupt on the angular rate of the given axis falling
+    /// below their threshold.
+    #[inline(always)]
+    pub const fn low(mut self, axis: u8) -> Self {
+        if (axis & gyro::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 0 }
+        if (axis & gyro::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 2 }
+        if (axis & gyro::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 4 }
+
+        self
+    }
+
+    /// Sets the threshold of the given axis in dps.
+    /// The threshold is converted with the range of the device when applied.
+    #[inline(always)]
+    pub const fn threshold(mut self, axis: gyro::Axis, dps: f32) -> Self {
+        match axis {
+            gyro::Axis::XAxis => self.threshold[0] = dps,
+            gyro::Axis::YAxis => self.threshold[1] = dps,
+            gyro::Axis::ZAxis => self.threshold[2] = dps,
+        }
+
+        self
+    }
+
+    /// The interrupt is generated when all the enabled events occur.
+    #[inline(always)]
+    pub const fn and(mut self) -> Self {
+        self.cfg |= 1 << 7;
+        self
+    }
+
+    /// The interrupt is generated when any of the enabled events occur.
+    #[inline(always)]
+    pub const fn or(mut self) -> Self {
+        self.cfg &= !(1 << 7);
+        self
+    }
+
+    /// Enables / Disables latching the interrupt until the source is read.
+    #[inline(always)]
+    pub const fn latch(mut self, s: bool) -> Self {
+        if s { self.cfg |= 1 << 6 }
+        else { self.cfg &= !(1 << 6) }
+
+        self
+    }
+
+    /// Sets the minimum duration of the event in samples (7 bits).
+    /// If `wait` is set, the interrupt also waits the same duration before
+    /// falling after the event has ended.
+    #[inline(always)]
+    pub const fn duration(mut self, samples: u8, wait: bool) -> Self {
+        self.duration = samples & 0x7F;
+
+        if wait { self.duration |= 1 << 7 }
+
+        self
+    }
+
+    /// Returns the threshold registers values for the given range.
+    /// The values are ordered as XH, XL, YH, YL, ZH, ZL.
+    pub(super) fn thresholds(&self, range: gyro::Range) -> [u8; 6] {
+        let mut out = [0u8; 6];
+
+        for (i, dps) in self.threshold.iter().enumerate() {
+            // The threshold is an unsigned 15 bit value with the same
+            // sensitivity as the output data. The float conversion
+            // saturates, negative values become 0 and values over the
+            // range become the maximum threshold.
+            let raw = ((*dps / range.params()) as u16).min(0x7FFF);
+
+            out[2*i  ] = (raw >> 8) as u8;
+            out[2*i+1] = raw as u8;
+        }
+
+        out
+    }
+}
+
+impl const Default for Interrupt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptSource {
+    /// One or more interrupts have been generated.
+    pub active: bool,
+
+    /// X-axis high event.
+    pub xh: bool,
+    /// X-axis low event.
+    pub xl: bool,
+
+    /// Y-axis high event.
+    pub yh: bool,
+    /// Y-axis low event.
+    pub yl: bool,
+
+    /// Z-axis high event.
+    pub zh: bool,
+    /// Z-axis low event.
+    pub zl: bool,
+}
+
+impl core::convert::From<u8> for InterruptSource {
+    fn from(s: u8) -> InterruptSource {
+        InterruptSource {
+            active: (s & (1 << 6)) != 0,
+
+            xl: (s & (1 << 0)) != 0,
+            xh: (s & (1 << 1)) != 0,
+            yl: (s & (1 << 2)) != 0,
+            yh: (s & (1 << 3)) != 0,
+            zl: (s & (1 << 4)) != 0,
+            zh: (s & (1 << 5)) != 0,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_in_range() {
+        let int = Interrupt::new()
+            .threshold(gyro::Axis::XAxis, 1.0)
+            .threshold(gyro::Axis::YAxis, 7.035)
+            .threshold(gyro::Axis::ZAxis, 0.0);
+
+        // 1 dps / 8.75 mdps = 114.3
+        assert_eq!(int.thresholds(gyro::Range::Dps250)[0..2], [0x00, 0x72]);
+
+        // 7.035 dps / 70 mdps = 100.5
+        assert_eq!(int.thresholds(gyro::Range::Dps2000)[2..4], [0x00, 0x64]);
+
+        assert_eq!(int.thresholds(gyro::Range::Dps500)[4..6], [0x00, 0x00]);
+    }
+
+    #[test]
+    fn thresholds_boundary() {
+        let int = Interrupt::new()
+            .threshold(gyro::Axis::XAxis, 2293.6)
+            .threshold(gyro::Axis::YAxis, 2293.72);
+
+        // 2293.6 dps / 70 mdps = 32765.7
+        // 2293.72 dps / 70 mdps = 32767.4
+        assert_eq!(int.thresholds(gyro::Range::Dps2000)[0..4], [0x7F, 0xFD, 0x7F, 0xFF]);
+    }
+
+    #[test]
+    fn thresholds_out_of_range() {
+        let int = Interrupt::new()
+            .threshold(gyro::Axis::XAxis, 2400.0)
+            .threshold(gyro::Axis::YAxis, 1.0e9)
+            .threshold(gyro::Axis::ZAxis, -10.0);
+
+        assert_eq!(
+            int.thresholds(gyro::Range::Dps2000),
+            [0x7F, 0xFF, 0x7F, 0xFF, 0x00, 0x00]
+        );
+    }
+}
@@ -5,189 +5,611 @@ pub mod gyro;
 
 mod config;
 mod error;
+mod interface;
+mod interrupt;
+
+#[cfg(feature = "async")]
+mod asynch;
 
 
 pub use self::config::Config;
 pub use self::error::Error;
+pub use self::interface::{ Interface, I2cInterface, SpiInterface, SpiError };
+pub use self::interrupt::{ Interrupt, InterruptSource };
+
+#[cfg(feature = "async")]
+pub use self::asynch::L3gd20Async;
+#[cfg(feature = "async")]
+pub use self::interface::AsyncInterface;
 
-use crate::{ Gyroscope, Thermometer };
+use crate::{ Gyroscope, Reading, Saturation, SelfTest, Thermometer };
+use crate::units::{ self, Celsius, MilliCelsius, MilliDegreesPerSecond, RadiansPerSecond, Vector3 };
 
-use core::{ mem::MaybeUninit, ops::* };
+use core::ops::*;
 
-use embedded_hal::i2c::SevenBitAddress;
-use embedded_hal::i2c::blocking::{
-    Write, WriteRead
-};
+use embedded_hal::delay::DelayNs;
 
 
 /// L3GD20 driver.
 pub struct L3gd20<I> {
-    /// I2C interface.
+    /// Register access interface (I2C or SPI).
     interface: I,
 
-    /// Address of the device.
-    /// L3GD20 has a selectable address bit.
-    addr: u8,
-
     /// Gyroscope range.
     gyro: gyro::Range,
+
+    /// Last configuration applied to the device.
+    cfg: Config,
+
+    /// Saturation policy.
+    saturation: Saturation,
+
+    /// Saturation flags of the last normalized sample.
+    saturated: [bool; 3],
+
+    /// Temperature in ºC that corresponds to a raw output of 0.
+    temp_offset: f32,
+
+    /// Temperature offset rounded to mºC for the fixed point output.
+    temp_offset_mc: i32,
 }
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> L3gd20<I> {
+impl<I> L3gd20<I> {
+    /// Creates the driver state without accessing the device.
+    fn build(interface: I, cfg: Config) -> Self {
+        L3gd20 {
+            interface, cfg,
+            gyro: cfg.params(),
+            saturation: Saturation::Error,
+            saturated: [false; 3],
+            temp_offset: gyro::TEMP_OFFSET,
+            temp_offset_mc: units::round(gyro::TEMP_OFFSET * 1000.0),
+        }
+    }
 
-    /// Creates a new driver and configures the device.
-    /// As the address of this sensor is hardware configurable, the lowbit flag
-    /// indicates that the 0x69 address is used, rather than the 0x68.
-    pub fn create(interface: I, cfg: Config, lowbit: bool) -> Result<Self, <I as Write>::Error> {
-        // Get the parameters of the accelerometer and magnetometer.
-        let gyro = cfg.params();
+    /// Returns the last configuration applied to the device.
+    pub fn config(&self) -> Config {
+        self.cfg
+    }
+
+    /// Sets the policy applied to saturated axis in the normalized output.
+    pub fn set_saturation(&mut self, policy: Saturation) {
+        self.saturation = policy;
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized sample.
+    pub fn saturated(&self) -> [bool; 3] {
+        self.saturated
+    }
+
+    /// Sets the temperature in ºC that corresponds to a raw output of 0.
+    /// Defaults to 25 ºC.
+    pub fn set_temperature_offset(&mut self, offset: f32) {
+        self.temp_offset = offset;
+        self.temp_offset_mc = units::round(offset * 1000.0);
+    }
+
+    /// Returns the temperature offset in ºC.
+    pub fn temperature_offset(&self) -> f32 {
+        self.temp_offset
+    }
+
+    /// Stores the temperature offset that makes the raw output equal to the
+    /// known temperature in ºC.
+    fn calibrate(&mut self, raw: i8, known: f32) {
+        self.temp_offset = known + raw as f32;
+        self.temp_offset_mc = units::round(known * 1000.0) + (raw as i32 * 1000);
+    }
+
+    /// Applies the saturation policy to the raw data.
+    fn saturate<E>(&mut self, raw: [i16; 3]) -> Result<[i16; 3], Error<E>> {
+        let mut raw = raw;
+
+        match self.saturation.apply(&mut raw, -32759, 32759) {
+            Err(axis) => return Err( Error::overflow(axis) ),
+            Ok(flags) => self.saturated = flags,
+        };
+
+        Ok(raw)
+    }
+
+    /// Applies the saturation policy and converts the raw data to rad/s.
+    fn normalize<E, F>(&mut self, raw: [i16; 3]) -> Result<Vector3<RadiansPerSecond<F>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        // Check for saturation.
+        let [rawx, rawy, rawz] = self.saturate(raw)?;
+
+        // Get the resolution multiplier.
+        let mul = self.gyro.params();
+
+        let gyrox = F::from(rawx) * F::from(mul) * F::from(0.017453293);
+        let gyroy = F::from(rawy) * F::from(mul) * F::from(0.017453293);
+        let gyroz = F::from(rawz) * F::from(mul) * F::from(0.017453293);
+
+        Ok( Vector3::new(RadiansPerSecond(gyrox), RadiansPerSecond(gyroy), RadiansPerSecond(gyroz)) )
+    }
+
+    /// Applies the saturation policy and converts the raw data to mdps
+    /// with integer arithmetic.
+    fn normalize_fixed<E>(&mut self, raw: [i16; 3]) -> Result<Vector3<MilliDegreesPerSecond>, Error<E>> {
+        let raw = self.saturate(raw)?;
+
+        // Get the resolution fraction.
+        let (num, den) = self.gyro.mdps();
+
+        Ok( Vector3::from(raw).map(|v| MilliDegreesPerSecond( units::scale(v as i32, num, den) )) )
+    }
+
+    /// Converts the raw temperature data to ºC, -1 LSB / ºC.
+    fn celsius<F>(&self, raw: i8) -> Celsius<F>
+        where F: Clone + Copy +
+            From<f32> + From<i8> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        Celsius( F::from(self.temp_offset) - F::from(raw) )
+    }
+
+    /// Converts the raw temperature data to mºC, -1 LSB / ºC.
+    fn milli_celsius(&self, raw: i8) -> MilliCelsius {
+        MilliCelsius( self.temp_offset_mc - (raw as i32 * 1000) )
+    }
+}
 
-        // Build address.
-        let addr = if lowbit { 0x69 } else { 0x68 };
 
+
+impl<I: Interface> L3gd20<I> {
+
+    /// Creates a new driver and configures the device.
+    /// Fails with `Error::WrongDevice` if the device does not identify as
+    /// an L3GD20 or L3GD20H.
+    pub fn create(interface: I, cfg: Config) -> Result<Self, Error<I::Error>> {
         // Create the device.
-        let mut device = L3gd20 { interface, addr, gyro };
+        let mut device = L3gd20::build(interface, cfg);
+
+        // Check the identity of the device.
+        device.identify()?;
+
+        // Configure the device.
+        match device.configure() {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok(device)
+    }
 
-        // Configure gyroscope module.
-        device.wr(&[gyro::Register::Ctrl1 as u8, cfg.ctrl1])?;
-        device.wr(&[gyro::Register::Ctrl2 as u8, cfg.ctrl2])?;
-        device.wr(&[gyro::Register::Ctrl3 as u8, cfg.ctrl3])?;
-        device.wr(&[gyro::Register::Ctrl4 as u8, cfg.ctrl4])?;
-        device.wr(&[gyro::Register::Ctrl5 as u8, cfg.ctrl5])?;
+    /// Creates a new driver for an already configured device.
+    /// The configuration is read back from the device instead of written.
+    /// Fails with `Error::WrongDevice` if the device does not identify as
+    /// an L3GD20 or L3GD20H.
+    pub fn attach(interface: I) -> Result<Self, Error<I::Error>> {
+        // Create the device with a placeholder configuration.
+        let mut device = L3gd20::build(interface, Config::default());
+
+        // Check the identity of the device.
+        device.identify()?;
+
+        // Adopt the configuration of the device.
+        device.cfg = device.read_config()?;
+        device.gyro = device.cfg.params();
 
         Ok(device)
     }
 
+    /// Reads the current configuration from the device registers.
+    pub fn read_config(&mut self) -> Result<Config, Error<I::Error>> {
+        let mut ctrl = [0u8; 5];
+        let mut fifo = [0u8; 1];
+
+        match self.rd(gyro::Register::Ctrl1, &mut ctrl) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.rd(gyro::Register::FIFOCtrl, &mut fifo) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( Config::from_registers(ctrl, fifo[0]) )
+    }
+
+    /// Reads the WhoAmI register and checks it against the known identities.
+    fn identify(&mut self) -> Result<(), Error<I::Error>> {
+        let mut data = [0u8; 1];
+
+        match self.rd(gyro::Register::WhoAmI, &mut data) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        if !gyro::IDENTITY.contains(&data[0]) {
+            return Err( Error::WrongDevice { found: data[0] } )
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last applied configuration to the device.
+    fn configure(&mut self) -> Result<(), I::Error> {
+        // Configure the gyroscope module and the FIFO.
+        for (reg, value) in self.cfg.registers() {
+            self.wr(reg, value)?;
+        }
+
+        Ok(())
+    }
+
     /// Sleeps the device.
-    /// To wake it up, the user must select again the output data rate
-    /// or reset the device.
-    pub fn sleep(&mut self) -> Result<(), <I as Write>::Error> {
+    /// To wake it up, the user must select again the output data rate,
+    /// call `wake` or reset the device.
+    pub fn sleep(&mut self) -> Result<(), I::Error> {
         // Configure accelrometer module.
-        self.wr(&[gyro::Register::Ctrl1 as u8, 0b00001000])?;
+        self.wr(gyro::Register::Ctrl1, 0b00001000)?;
 
         Ok(())
     }
-}
 
+    /// Wakes the device up from sleep with the last applied configuration.
+    pub fn wake(&mut self) -> Result<(), I::Error> {
+        self.wr(gyro::Register::Ctrl1, self.cfg.ctrl1)
+    }
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Gyroscope for L3gd20<I> {
-    type Error = Error<<I as WriteRead>::Error>;
-    type Output = i16;
+    /// Applies a new configuration to the device.
+    /// Only the registers that differ from the last applied configuration
+    /// are written. The interrupt thresholds are not rescaled if the range
+    /// changes.
+    pub fn apply(&mut self, cfg: Config) -> Result<(), I::Error> {
+        let new = cfg.registers();
+
+        for (i, (reg, old)) in self.cfg.registers().into_iter().enumerate() {
+            if old == new[i].1 { continue }
+
+            self.wr(reg, new[i].1)?;
+
+            // Keep the cache in sync with the registers already written.
+            self.cfg.set(i, new[i].1);
+            self.gyro = self.cfg.params();
+        }
+
+        Ok(())
+    }
+
+    /// Reboots the memory content of the device, waits for the boot to end
+    /// and restores the last applied configuration.
+    /// The interrupt generator and the Reference register are not restored.
+    pub fn reboot<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), I::Error> {
+        // Set the BOOT bit.
+        self.wr(gyro::Register::Ctrl5, self.cfg.ctrl5 | (1 << 7))?;
+
+        // Wait for the boot procedure.
+        delay.delay_us(gyro::BOOT_TIME);
+
+        // Restore the configuration.
+        self.configure()
+    }
+
+    /// Runs the built-in self-test at the current range and data rate.
+    /// Averages the output with the self-test disabled and enabled and
+    /// checks the change of each axis against the given minimum and maximum
+    /// in dps. The datasheet only gives the typical change, the limits are
+    /// provided by the caller (e.g. from the end-of-line test specification).
+    /// The device must be still during the test. The configuration is
+    /// restored afterwards.
+    pub fn self_test<D: DelayNs>(&mut self, delay: &mut D, limits: (f32, f32)) -> Result<SelfTest, Error<I::Error>> {
+        // Get the sample period in us.
+        let period = 1_000_000 / gyro::DataRate::from( (self.cfg.ctrl1 >> 6) & 0x3 ).hz();
+
+        // Average the output with the self-test disabled.
+        let base = self.average(delay, period)?;
+
+        // Enable the self-test (positive sign) and let the output settle.
+        match self.wr(gyro::Register::Ctrl4, (self.cfg.ctrl4 & !(0x3 << 1)) | (0b01 << 1)) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        delay.delay_us(gyro::SELFTEST_SETTLE);
+
+        let test = self.average(delay, period);
+
+        // Restore the configuration even if the measurement failed.
+        let restore = self.wr(gyro::Register::Ctrl4, self.cfg.ctrl4);
+
+        let test = test?;
+
+        match restore {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        delay.delay_us(gyro::SELFTEST_SETTLE);
+
+        // Convert the averages to dps and check the limits.
+        let mul = self.gyro.params();
+
+        Ok( SelfTest::new(
+            [base[0] * mul, base[1] * mul, base[2] * mul],
+            [test[0] * mul, test[1] * mul, test[2] * mul],
+            limits,
+        ))
+    }
+
+    /// Averages the raw output over the self-test samples.
+    /// The first sample after a configuration change is discarded.
+    fn average<D: DelayNs>(&mut self, delay: &mut D, period: u32) -> Result<[f32; 3], Error<I::Error>> {
+        delay.delay_us(period);
+        self.gyroraw()?;
+
+        let mut sum = [0i32; 3];
+
+        for _ in 0..gyro::SELFTEST_SAMPLES {
+            delay.delay_us(period);
+
+            let raw = self.gyroraw()?;
+
+            for i in 0..3 { sum[i] += raw[i] as i32 }
+        }
+
+        let n = gyro::SELFTEST_SAMPLES as f32;
+
+        Ok([sum[0] as f32 / n, sum[1] as f32 / n, sum[2] as f32 / n])
+    }
+
+    /// Changes the gyroscope scale.
+    pub fn set_scale(&mut self, scale: gyro::Scale) -> Result<(), I::Error> {
+        self.apply( self.cfg.scale(scale) )
+    }
+
+    /// Changes the gyroscope output data rate.
+    pub fn set_datarate(&mut self, rate: gyro::DataRate) -> Result<(), I::Error> {
+        self.apply( self.cfg.datarate(rate) )
+    }
+
+    /// Changes the gyroscope filter bandwidth.
+    pub fn set_bandwidth(&mut self, bandwidth: gyro::Bandwidth) -> Result<(), I::Error> {
+        self.apply( self.cfg.bandwidth(bandwidth) )
+    }
+
+    /// One-point calibration of the temperature sensor.
+    /// Reads the current output and stores the offset that makes it equal
+    /// to the given known temperature in ºC.
+    pub fn calibrate_temperature(&mut self, known: f32) -> Result<(), Error<I::Error>> {
+        let raw = self.tempraw()?;
+
+        self.calibrate(raw, known);
+
+        Ok(())
+    }
+
+    /// Reads the FIFO status.
+    pub fn fifo_status(&mut self) -> Result<gyro::FIFOStatus, Error<I::Error>> {
+        let mut data = [0u8; 1];
+
+        match self.rd(gyro::Register::FIFOSrc, &mut data) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( gyro::FIFOStatus::from(data[0]) )
+    }
+
+    /// Drains the FIFO into the given buffer with a single burst read.
+    /// Returns the number of samples read. If the buffer is smaller than the
+    /// FIFO level, the remaining samples are kept in the FIFO.
+    pub fn fifo_readraw(&mut self, buffer: &mut [[i16; 3]]) -> Result<usize, Error<I::Error>> {
+        // Get the amount of samples to read.
+        let n = core::cmp::min(self.fifo_status()?.level, buffer.len());
+
+        if n == 0 { return Ok(0) }
 
-    fn gyroraw(&mut self) -> Result<[i16; 3], Error<<I as WriteRead>::Error>> {
         // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut data = [0u8; 6 * gyro::FIFO_DEPTH];
 
-        // Read in the output data.
-        match self.wrrd(&[gyro::Register::OutXL as u8 | (1 << 7)], &mut data) {
+        // With the FIFO enabled the address rolls back from OutZH to OutXL,
+        // so all samples can be read in one auto-increment transaction.
+        match self.rd(gyro::Register::OutXL, &mut data[..6 * n]) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        for (sample, raw) in buffer.iter_mut().zip(data[..6 * n].chunks_exact(6)) {
+            *sample = gyro::decode(raw);
+        }
+
+        Ok(n)
+    }
+
+    /// Configures the threshold interrupt generator and routes it to the
+    /// INT1 pin.
+    pub fn interrupt(&mut self, int: Interrupt) -> Result<(), I::Error> {
+        // Get the threshold registers for the current range.
+        let ths = int.thresholds(self.gyro);
+
+        // Configure the interrupt generator.
+        self.wr(gyro::Register::IntCfg, int.cfg)?;
+
+        self.wr(gyro::Register::IntXHTsh, ths[0])?;
+        self.wr(gyro::Register::IntXLTsh, ths[1])?;
+        self.wr(gyro::Register::IntYHTsh, ths[2])?;
+        self.wr(gyro::Register::IntYLTsh, ths[3])?;
+        self.wr(gyro::Register::IntZHTsh, ths[4])?;
+        self.wr(gyro::Register::IntZLTsh, ths[5])?;
+
+        self.wr(gyro::Register::IntDur, int.duration)?;
+
+        // Route the interrupt to the INT1 pin.
+        self.cfg.ctrl3 |= 1 << 7;
+        self.wr(gyro::Register::Ctrl3, self.cfg.ctrl3)?;
+
+        Ok(())
+    }
+
+    /// Writes the Reference register used by the high pass filter in
+    /// `HighPassMode::Reference`. The value uses the output data sensitivity.
+    pub fn reference(&mut self, value: u8) -> Result<(), I::Error> {
+        self.wr(gyro::Register::Reference, value)
+    }
+
+    /// Reads and decodes the interrupt source.
+    /// If the interrupt is latched, reading the source clears it.
+    pub fn interrupt_source(&mut self) -> Result<InterruptSource, Error<I::Error>> {
+        let mut data = [0u8; 1];
+
+        match self.rd(gyro::Register::IntSrc, &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( (data[0] as u16) | ((data[1] as u16) << 8) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( (data[2] as u16) | ((data[3] as u16) << 8) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( (data[4] as u16) | ((data[5] as u16) << 8) ) };
+        Ok( InterruptSource::from(data[0]) )
+    }
+
+    /// Reads the gyroscope raw data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read.
+    pub fn try_gyroraw(&mut self) -> nb::Result<Reading<[i16; 3]>, Error<I::Error>> {
+        // Read in the status and the output data in one burst.
+        let mut data = [0u8; 7];
+
+        match self.rd(gyro::Register::Status, &mut data) {
+            Err(e) => return Err( nb::Error::Other( Error::BusError(e) ) ),
+            _ => (),
+        };
+
+        // Check for new data available (ZYXDA).
+        if (data[0] & (1 << 3)) == 0 { return Err( nb::Error::WouldBlock ) }
 
-        Ok([rawx, rawy, rawz])
+        Ok( Reading { data: gyro::decode(&data[1..]), overrun: (data[0] & (1 << 7)) != 0 } )
     }
 
-    fn gyro<F>(&mut self) -> Result<[F; 3], Error<<I as WriteRead>::Error>>
+    /// Reads the normalized gyroscope data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read.
+    pub fn try_gyro<F>(&mut self) -> nb::Result<Reading<Vector3<RadiansPerSecond<F>>>, Error<I::Error>>
         where F: Clone + Copy +
-            From<f32> + From<Self::Output> +
+            From<f32> + From<i16> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>
     {
+        let Reading { data, overrun } = self.try_gyroraw()?;
+
+        Ok( Reading { data: self.normalize(data)?, overrun } )
+    }
+}
+
+
+impl<I: Interface> Gyroscope for L3gd20<I> {
+    type Error = Error<I::Error>;
+    type Output = i16;
+
+    fn gyroraw(&mut self) -> Result<[i16; 3], Error<I::Error>> {
         // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut data = [0u8; 6];
 
         // Read in the output data.
-        match self.wrrd(&[gyro::Register::OutXL as u8 | (1 << 7)], &mut data) {
+        match self.rd(gyro::Register::OutXL, &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( (data[0] as u16) | ((data[1] as u16) << 8) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( (data[2] as u16) | ((data[3] as u16) << 8) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( (data[4] as u16) | ((data[5] as u16) << 8) ) };
+        Ok( gyro::decode(&data) )
+    }
 
-        // Check for saturation.
-        if (rawx >= 32760) || (rawx <= -32760) { return Err( Error::RangeOverflowX ) }
-        if (rawy >= 32760) || (rawy <= -32760) { return Err( Error::RangeOverflowY ) }
-        //if (rawz >= 32760) || (rawz <= -32760) { return Err( Error::RangeOverflowZ ) }
+    fn gyro<F>(&mut self) -> Result<Vector3<RadiansPerSecond<F>>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.gyroraw()?;
 
-        // Get the resolution multiplier.
-        let mul = self.gyro.params();
+        self.normalize(raw)
+    }
 
-        let gyrox = F::from(rawx) * F::from(mul) * F::from(0.017453293);
-        let gyroy = F::from(rawy) * F::from(mul) * F::from(0.017453293);
-        let gyroz = F::from(rawz) * F::from(mul) * F::from(0.017453293);
+    fn gyro_mdps(&mut self) -> Result<Vector3<MilliDegreesPerSecond>, Error<I::Error>> {
+        let raw = self.gyroraw()?;
 
-        Ok([gyrox, gyroy, gyroz])
+        self.normalize_fixed(raw)
     }
 }
 
 
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Thermometer for L3gd20<I> {
-    type Error = Error<<I as WriteRead>::Error>;
+impl<I: Interface> Thermometer for L3gd20<I> {
+    type Error = Error<I::Error>;
     type Output = i8;
 
-    fn tempraw(&mut self) -> Result<i8, Error<<I as WriteRead>::Error>> {
+    fn tempraw(&mut self) -> Result<i8, Error<I::Error>> {
         // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 1] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut data = [0u8; 1];
 
         // Read in the output data.
-        match self.wrrd(&[gyro::Register::TempOut as u8], &mut data) {
+        match self.rd(gyro::Register::TempOut, &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        Ok( unsafe { core::mem::transmute(data) } )
+        Ok( data[0] as i8 )
     }
 
-    fn temp<F>(&mut self) -> Result<F, Error<<I as WriteRead>::Error>>
+    fn temp<F>(&mut self) -> Result<Celsius<F>, Error<I::Error>>
         where F: Clone + Copy +
             From<f32> + From<Self::Output> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>
     {
-        // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 1] = unsafe { MaybeUninit::uninit().assume_init() };
-
-        // Read in the output data.
-        match self.wrrd(&[gyro::Register::TempOut as u8], &mut data) {
-            Err(e) => return Err( Error::BusError(e) ),
-            _ => (),
-        };
+        let raw = self.tempraw()?;
 
-        let raw: i8 = unsafe { core::mem::transmute(data) };
+        Ok( self.celsius(raw) )
+    }
 
-        let temp = F::from(raw);
+    fn temp_mc(&mut self) -> Result<MilliCelsius, Error<I::Error>> {
+        let raw = self.tempraw()?;
 
-        Ok(temp)
+        Ok( self.milli_celsius(raw) )
     }
 }
 
 
 
-impl<I: Write<SevenBitAddress>> L3gd20<I> {
+impl<I: Interface> L3gd20<I> {
     /// Internal write function.
     #[inline(always)]
-    pub(crate) fn wr(&mut self, bytes: &[u8]) -> Result<(), I::Error> {
-        self.interface.write(self.addr, bytes)
+    pub(crate) fn wr(&mut self, reg: gyro::Register, value: u8) -> Result<(), I::Error> {
+        self.interface.write(reg as u8, value)
     }
-}
 
-impl<I: WriteRead<SevenBitAddress>> L3gd20<I> {
-    /// Internal write function.
+    /// Internal read function.
     #[inline(always)]
-    pub(crate) fn wrrd(&mut self, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I::Error> {
-        self.interface.write_read(self.addr, bytes, buffer)
+    pub(crate) fn rd(&mut self, reg: gyro::Register, buffer: &mut [u8]) -> Result<(), I::Error> {
+        self.interface.read(reg as u8, buffer)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_offset() {
+        let mut device = L3gd20::build((), Config::default());
+
+        assert_eq!(device.milli_celsius(0), MilliCelsius(25_000));
+
+        device.set_temperature_offset(21.8);
+        assert_eq!(device.temperature_offset(), 21.8);
+        assert_eq!(device.milli_celsius(2), MilliCelsius(19_800));
+
+        // -1 LSB / ºC.
+        device.calibrate(-3, 22.4);
+        assert_eq!(device.milli_celsius(-3), MilliCelsius(22_400));
+        assert_eq!(device.celsius::<f32>(-3).0, 22.4);
     }
 }
@@ -4,6 +4,9 @@
 
 #![allow(incomplete_features)]
 
+// Bus errors are returned from a `match` with a single `Err` arm.
+#![allow(clippy::single_match)]
+
 
 #![feature(adt_const_params)]
 #![feature(const_trait_impl)]
@@ -16,6 +19,24 @@ pub use self::gyro::Gyroscope;
 pub use self::mag::Magnetometer;
 pub use self::temp::Thermometer;
 
+pub use self::imu::{ Clock, Imu, ImuSample };
+
+#[cfg(feature = "async")]
+pub use self::accel::AsyncAccelerometer;
+#[cfg(feature = "async")]
+pub use self::gyro::AsyncGyroscope;
+#[cfg(feature = "async")]
+pub use self::mag::AsyncMagnetometer;
+#[cfg(feature = "async")]
+pub use self::temp::AsyncThermometer;
+
+pub use self::orientation::Orientation;
+pub use self::reading::Reading;
+pub use self::saturation::Saturation;
+pub use self::selftest::SelfTest;
+
+pub use self::units::Vector3;
+
 
 
 /// Module for accelerometers and combined peripherals.
@@ -30,7 +51,29 @@ mod mag;
 /// Module for all thermometers and combined peripherals.
 mod temp;
 
+/// Module for inertial measurement units.
+mod imu;
+
+/// Module for the orientation recognition.
+mod orientation;
+
+/// Module for the data-ready aware sensor readings.
+mod reading;
+
+/// Module for the handling of saturated outputs.
+mod saturation;
+
+/// Module for the results of the built-in self-tests.
+mod selftest;
+
+
+
+/// Physical units of the normalized outputs.
+pub mod units;
+
 
 
+pub mod composite;
+pub mod mounting;
 pub mod l3gd20;
 pub mod lsm303dlhc;
\ No newline at end of file
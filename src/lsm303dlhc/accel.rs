@@ -10,6 +10,9 @@ use embedded_hal::i2c::SevenBitAddress;
 /// Accelerometer module I2C address.
 pub(crate) const ACCEL: SevenBitAddress = 0b0011001;
 
+/// Time in us to wait after rebooting the memory content.
+pub(crate) const BOOT_TIME: u32 = 5_000;
+
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -24,6 +27,8 @@ pub enum Axis {
     ZAxis = 0b100,
 }
 
+// The axis masks are combined with a bitwise OR.
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<Axis> for Axis {
     type Output = u8;
 
@@ -33,6 +38,7 @@ impl const core::ops::Add<Axis> for Axis {
     }
 }
 
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<u8> for Axis {
     type Output = u8;
 
@@ -42,6 +48,7 @@ impl const core::ops::Add<u8> for Axis {
     }
 }
 
+#[allow(clippy::suspicious_arithmetic_impl)]
 impl const core::ops::Add<Axis> for u8 {
     type Output = u8;
 
@@ -78,6 +85,59 @@ pub enum DataRate {
 }
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FIFOMode {
+    /// FIFO is bypassed.
+    Bypass = 0b00,
+
+    /// FIFO mode. Stops collecting data when full.
+    Fifo = 0b01,
+
+    /// Stream mode. The oldest samples are overwritten when full.
+    Stream = 0b10,
+
+    /// Stream to FIFO mode. Works in Stream mode until the INT1 generator
+    /// triggers, then switches to FIFO mode.
+    StreamToFifo = 0b11,
+}
+
+
+
+/// Number of samples the FIFO can hold.
+pub const FIFO_DEPTH: usize = 32;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FIFOStatus {
+    /// Number of unread samples stored in the FIFO.
+    pub level: usize,
+
+    /// The FIFO level is equal or higher than the watermark level.
+    pub watermark: bool,
+
+    /// The FIFO is full and the oldest samples are being overwritten.
+    pub overrun: bool,
+}
+
+impl core::convert::From<u8> for FIFOStatus {
+    fn from(s: u8) -> FIFOStatus {
+        let watermark = (s & (1 << 7)) != 0;
+        let overrun   = (s & (1 << 6)) != 0;
+        let empty     = (s & (1 << 5)) != 0;
+
+        // The stored data level only has 5 bits, a full FIFO is signaled
+        // through the overrun flag.
+        let level = if empty { 0 }
+            else if overrun { FIFO_DEPTH }
+            else { (s & 0x1F) as usize };
+
+        FIFOStatus { level, watermark, overrun }
+    }
+}
+
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Range {
@@ -89,6 +149,19 @@ pub enum Range {
 
 pub type Scale = Range;
 
+impl Range {
+    /// Returns the LSB of the interrupt and click thresholds in mg.
+    #[inline]
+    pub(super) fn threshold(&self) -> u16 {
+        match *self {
+            Range::G2  =>  16,
+            Range::G4  =>  32,
+            Range::G8  =>  62,
+            Range::G16 => 186,
+        }
+    }
+}
+
 impl core::convert::From<u8> for Range {
     fn from(s: u8) -> Range {
         match s {
@@ -198,33 +271,108 @@ pub enum Mode {
 
 impl Mode {
     /// Returns the parameters to calculate the real acceleration.
+    /// The sensitivity in g / LSB is derived from `ug`.
     #[inline]
     pub(super) fn params(&self, range: Range) -> (usize, f32) {
+        (self.shift(), self.ug(range) as f32 / 1_000_000.0)
+    }
+
+    /// Returns the number of unused low bits of the left aligned output data.
+    #[inline]
+    pub(super) fn shift(&self) -> usize {
+        match *self {
+            Mode::HighResolution => 4,
+            Mode::Normal         => 6,
+            Mode::LowPower       => 8,
+        }
+    }
+
+    /// Returns the sensitivity of the shifted output data in ug / LSB.
+    #[inline]
+    pub(super) fn ug(&self, range: Range) -> i32 {
         match *self {
-            // LSB values in high resolution mode.
             Mode::HighResolution => match range {
-                Range::G2  => (4, 0.00098),
-                Range::G4  => (4, 0.00195),
-                Range::G8  => (4, 0.00390),
-                Range::G16 => (4, 0.01172),
+                Range::G2  =>    980,
+                Range::G4  =>   1950,
+                Range::G8  =>   3900,
+                Range::G16 =>  11720,
             },
 
-            // LSB values in normal mode.
             Mode::Normal => match range {
-                Range::G2  => (6, 0.00390),
-                Range::G4  => (6, 0.00782),
-                Range::G8  => (6, 0.01563),
-                Range::G16 => (6, 0.04690),
+                Range::G2  =>   3900,
+                Range::G4  =>   7820,
+                Range::G8  =>  15630,
+                Range::G16 =>  46900,
             },
 
-            // LSB values in low power mode.
             Mode::LowPower => match range {
-                Range::G2  => (8, 0.01563),
-                Range::G4  => (8, 0.03126),
-                Range::G8  => (8, 0.06252),
-                Range::G16 => (8, 0.18758),
+                Range::G2  =>  15630,
+                Range::G4  =>  31260,
+                Range::G8  =>  62520,
+                Range::G16 => 187580,
             },
         }
     }
+
+    /// Returns the maximum value of the shifted output data.
+    /// Values at either end of the output range are saturated.
+    #[inline]
+    pub(super) fn limit(&self) -> i16 {
+        match *self {
+            Mode::HighResolution => 2047,
+            Mode::Normal         =>  511,
+            Mode::LowPower       =>  127,
+        }
+    }
 }
 
+
+
+/// Decodes a little endian X, Y and Z sample.
+#[inline(always)]
+pub(super) fn decode(data: &[u8]) -> [i16; 3] {
+    [
+        i16::from_le_bytes([data[0], data[1]]),
+        i16::from_le_bytes([data[2], data[3]]),
+        i16::from_le_bytes([data[4], data[5]]),
+    ]
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_status() {
+        assert_eq!(FIFOStatus::from(0b0010_0000), FIFOStatus { level:  0, watermark: false, overrun: false });
+        assert_eq!(FIFOStatus::from(0b0001_0000), FIFOStatus { level: 16, watermark: false, overrun: false });
+        assert_eq!(FIFOStatus::from(0b1001_1111), FIFOStatus { level: 31, watermark: true,  overrun: false });
+
+        // A full FIFO reads 0 stored samples with the overrun flag set.
+        assert_eq!(FIFOStatus::from(0b1100_0000), FIFOStatus { level: FIFO_DEPTH, watermark: true, overrun: true });
+    }
+
+    #[test]
+    fn mode_sensitivity() {
+        // Sensitivity in mg / LSB of each mode and range.
+        let table = [
+            (Mode::HighResolution, 4, [0.98, 1.95,  3.90,  11.72]),
+            (Mode::Normal,         6, [3.90, 7.82, 15.63,  46.90]),
+            (Mode::LowPower,       8, [15.63, 31.26, 62.52, 187.58]),
+        ];
+
+        let ranges = [Range::G2, Range::G4, Range::G8, Range::G16];
+
+        for (mode, shift, mg) in table {
+            for (range, mg) in ranges.iter().zip(mg) {
+                assert_eq!(mode.ug(*range), (mg * 1000.0f64).round() as i32);
+
+                let (s, lsb) = mode.params(*range);
+                assert_eq!(s, shift);
+                assert!((lsb - (mg / 1000.0) as f32).abs() < 1e-9);
+            }
+        }
+    }
+}
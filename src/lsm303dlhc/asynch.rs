@@ -0,0 +1,353 @@
+//! Async LSM303DLHC driver.
+//! Shares the configuration, register map and conversions with the blocking
+//! `Lsm303dlhc` driver.
+
+
+use super::{ accel, mag, Config, Error, Lsm303dlhc };
+
+use crate::{ AsyncAccelerometer, AsyncMagnetometer, AsyncThermometer, Saturation };
+use crate::units::{ Celsius, MetersPerSecondSquared, MicroTesla, MilliCelsius, MilliG, NanoTesla, Vector3 };
+
+use core::ops::*;
+
+use embedded_hal_async::i2c::{ I2c, SevenBitAddress };
+
+
+
+/// Async LSM303DLHC driver.
+/// Covers the configuration, setters and the sensor traits. The FIFO,
+/// interrupts, click, free-fall and orientation detection, single
+/// measurements, reboot and non-blocking `try_*` reads are only available
+/// in the blocking `Lsm303dlhc` driver.
+pub struct Lsm303dlhcAsync<I>(Lsm303dlhc<I>);
+
+impl<I: I2c> Lsm303dlhcAsync<I> {
+
+    /// Creates a new driver and configures the device.
+    /// Fails with `Error::WrongDevice` if the magnetometer does not identify
+    /// as an LSM303DLHC.
+    pub async fn create(interface: I, cfg: Config) -> Result<Self, Error<I::Error>> {
+        // Create the device.
+        let mut device = Lsm303dlhcAsync( Lsm303dlhc::build(interface, cfg) );
+
+        // Check the identity of the device.
+        device.identify().await?;
+
+        // Configure the device.
+        match device.configure().await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok(device)
+    }
+
+    /// Creates a new driver for an already configured device.
+    /// The configuration is read back from the device instead of written.
+    /// Fails with `Error::WrongDevice` if the magnetometer does not identify
+    /// as an LSM303DLHC and with `Error::InvalidConfig` if the registers
+    /// do not hold a valid configuration.
+    pub async fn attach(interface: I) -> Result<Self, Error<I::Error>> {
+        // Create the device with a placeholder configuration.
+        let mut device = Lsm303dlhcAsync( Lsm303dlhc::build(interface, Config::default()) );
+
+        // Check the identity of the device.
+        device.identify().await?;
+
+        // Adopt the configuration of the device.
+        device.0.cfg = device.read_config().await?;
+        (device.0.accel, device.0.mag) = device.0.cfg.params();
+
+        Ok(device)
+    }
+
+    /// Reads the current configuration from the device registers.
+    /// Fails with `Error::InvalidConfig` if the magnetometer gain is not a
+    /// valid setting.
+    pub async fn read_config(&mut self) -> Result<Config, Error<I::Error>> {
+        let mut ctrl = [0u8; 6];
+        let mut fifo = [0u8; 1];
+        let mut cr   = [0u8; 3];
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::Ctrl1 as u8 | (1 << 7)], &mut ctrl).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::FIFOCtrl as u8], &mut fifo).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.wrrd(mag::MAG, &[mag::Register::Cra as u8 | (1 << 7)], &mut cr).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match Config::from_registers(ctrl, fifo[0], cr) {
+            Some(cfg) => Ok(cfg),
+            None => Err( Error::InvalidConfig ),
+        }
+    }
+
+    /// Reads the magnetometer ID registers and checks them against the
+    /// expected identity.
+    async fn identify(&mut self) -> Result<(), Error<I::Error>> {
+        let mut data = [0u8; 3];
+
+        match self.wrrd(mag::MAG, &[mag::Register::IdA as u8 | (1 << 7)], &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        if data != mag::IDENTITY {
+            return Err( Error::WrongDevice { found: data } )
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last applied configuration to the device.
+    async fn configure(&mut self) -> Result<(), I::Error> {
+        // Reset the magnetometer gain.
+        self.wr(mag::MAG, &[mag::Register::Crb as u8, 0x00]).await?;
+
+        // Configure the accelerometer module, its FIFO and the magnetometer
+        // gain, data rate, temperature and mode.
+        for (addr, reg, value) in self.0.cfg.registers() {
+            self.wr(addr, &[reg, value]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps the device.
+    /// To wake it up, the user must select again the output data rate,
+    /// call `wake` or reset the device.
+    pub async fn sleep(&mut self) -> Result<(), I::Error> {
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl1 as u8, 0b00001000]).await?;
+        self.wr(mag::MAG, &[mag::Register::Mr  as u8, mag::OperatingMode::Sleep as u8]).await?;
+
+        Ok(())
+    }
+
+    /// Wakes the device up from sleep with the last applied configuration.
+    pub async fn wake(&mut self) -> Result<(), I::Error> {
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl1 as u8, self.0.cfg.ctrl1]).await?;
+        self.wr(mag::MAG, &[mag::Register::Mr  as u8, self.0.cfg.mr]).await?;
+
+        Ok(())
+    }
+
+    /// Applies a new configuration to the device.
+    /// Only the registers that differ from the last applied configuration
+    /// are written.
+    pub async fn apply(&mut self, cfg: Config) -> Result<(), I::Error> {
+        let new = cfg.registers();
+
+        for (i, (addr, reg, old)) in self.0.cfg.registers().into_iter().enumerate() {
+            if old == new[i].2 { continue }
+
+            self.wr(addr, &[reg, new[i].2]).await?;
+
+            // Keep the cache in sync with the registers already written.
+            self.0.cfg.set(i, new[i].2);
+            (self.0.accel, self.0.mag) = self.0.cfg.params();
+        }
+
+        Ok(())
+    }
+
+    /// Changes the accelerometer and magnetometer scales.
+    pub async fn set_scale(&mut self, accel: Option<accel::Scale>, mag: Option<mag::Scale>) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.scale(accel, mag) ).await
+    }
+
+    /// Changes the accelerometer and magnetometer output data rates.
+    pub async fn set_datarate(&mut self, accel: Option<accel::DataRate>, mag: Option<mag::DataRate>) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.datarate(accel, mag) ).await
+    }
+
+    /// Changes the accelerometer mode.
+    pub async fn set_mode(&mut self, mode: accel::Mode) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.mode(mode) ).await
+    }
+
+    /// Changes the magnetometer operating mode.
+    pub async fn set_magmode(&mut self, mode: mag::OperatingMode) -> Result<(), I::Error> {
+        self.apply( self.0.cfg.magmode(mode) ).await
+    }
+
+    /// One-point calibration of the temperature sensor.
+    /// Reads the current output and stores the offset that makes it equal
+    /// to the given known temperature in ºC.
+    pub async fn calibrate_temperature(&mut self, known: f32) -> Result<(), Error<I::Error>> {
+        let raw = self.tempraw().await?;
+
+        self.0.calibrate(raw, known);
+
+        Ok(())
+    }
+
+    /// Internal write function.
+    #[inline(always)]
+    async fn wr(&mut self, a: SevenBitAddress, bytes: &[u8]) -> Result<(), I::Error> {
+        self.0.interface.write(a, bytes).await
+    }
+
+    /// Internal write-read function.
+    #[inline(always)]
+    async fn wrrd(&mut self, a: SevenBitAddress, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I::Error> {
+        self.0.interface.write_read(a, bytes, buffer).await
+    }
+}
+
+impl<I> Lsm303dlhcAsync<I> {
+    /// Returns the last configuration applied to the device.
+    pub fn config(&self) -> Config {
+        self.0.config()
+    }
+
+    /// Sets the policy applied to saturated axis in the normalized
+    /// accelerometer and magnetometer outputs.
+    pub fn set_saturation(&mut self, policy: Saturation) {
+        self.0.set_saturation(policy)
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized
+    /// accelerometer sample.
+    pub fn accel_saturated(&self) -> [bool; 3] {
+        self.0.accel_saturated()
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized
+    /// magnetometer sample.
+    pub fn mag_saturated(&self) -> [bool; 3] {
+        self.0.mag_saturated()
+    }
+
+    /// Sets the temperature in ºC that corresponds to a raw output of 0.
+    pub fn set_temperature_offset(&mut self, offset: f32) {
+        self.0.set_temperature_offset(offset)
+    }
+
+    /// Returns the temperature offset in ºC.
+    pub fn temperature_offset(&self) -> f32 {
+        self.0.temperature_offset()
+    }
+}
+
+
+
+impl<I: I2c> AsyncAccelerometer for Lsm303dlhcAsync<I> {
+    type Error = Error<I::Error>;
+    type Output = i16;
+
+    async fn accelraw(&mut self) -> Result<[i16; 3], Error<I::Error>> {
+        // Create the input buffer.
+        let mut data = [0u8; 6];
+
+        // Read in the output data.
+        match self.wrrd(accel::ACCEL, &[accel::Register::OutXL as u8 | (1 << 7)], &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( accel::decode(&data) )
+    }
+
+    async fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.accelraw().await?;
+
+        self.0.accel_normalize(raw)
+    }
+
+    async fn accel_mg(&mut self) -> Result<Vector3<MilliG>, Error<I::Error>> {
+        let raw = self.accelraw().await?;
+
+        self.0.accel_fixed(raw)
+    }
+}
+
+
+
+impl<I: I2c> AsyncMagnetometer for Lsm303dlhcAsync<I> {
+    type Error = Error<I::Error>;
+    type Output = i16;
+
+    async fn magraw(&mut self) -> Result<[i16; 3], Error<I::Error>> {
+        // Create the input buffer.
+        let mut data = [0u8; 6];
+
+        // Read in the output data.
+        match self.wrrd(mag::MAG, &[mag::Register::OutXH as u8 | (1 << 7)], &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( mag::decode(&data) )
+    }
+
+    async fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.magraw().await?;
+
+        self.0.mag_normalize(raw)
+    }
+
+    async fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, Error<I::Error>> {
+        let raw = self.magraw().await?;
+
+        self.0.mag_fixed(raw)
+    }
+}
+
+
+
+impl<I: I2c> AsyncThermometer for Lsm303dlhcAsync<I> {
+    type Error = Error<I::Error>;
+    type Output = i16;
+
+    async fn tempraw(&mut self) -> Result<i16, Error<I::Error>> {
+        // Check that the temperature sensor is enabled.
+        self.0.temperature_enabled()?;
+
+        // Create the input buffer.
+        let mut data = [0u8; 2];
+
+        // Read in both halves in a single transaction.
+        match self.wrrd(mag::MAG, &[mag::Register::TempOutH as u8 | (1 << 7)], &mut data).await {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        // Get the raw 12 bit data.
+        Ok( i16::from_be_bytes(data) >> 4 )
+    }
+
+    async fn temp<F>(&mut self) -> Result<Celsius<F>, Error<I::Error>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.tempraw().await?;
+
+        Ok( self.0.celsius(raw) )
+    }
+
+    async fn temp_mc(&mut self) -> Result<MilliCelsius, Error<I::Error>> {
+        let raw = self.tempraw().await?;
+
+        Ok( self.0.milli_celsius(raw) )
+    }
+}
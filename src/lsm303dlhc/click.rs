@@ -0,0 +1,201 @@
+//! Click (tap) detection configuration for the LSM303DLHC device.
+
+
+use super::accel;
+use super::interrupt::Pin;
+
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Click {
+    /// Click Configuration register.
+    pub(super) cfg: u8,
+
+    /// Threshold in mg.
+    pub(super) threshold: u16,
+
+    /// Maximum duration of a click in ms.
+    pub(super) limit: u16,
+
+    /// Quiet time after the first click in ms.
+    pub(super) latency: u16,
+
+    /// Time window for the second click in ms.
+    pub(super) window: u16,
+
+    /// Output pin of the interrupt.
+    pub(super) pin: Option<Pin>,
+}
+
+impl Click {
+    /// Static initializer.
+    /// All clicks are disabled and the interrupt is not routed to any pin.
+    pub const fn new() -> Self {
+        Click {
+            cfg: 0b00000000,
+            threshold: 0,
+            limit: 0,
+            latency: 0,
+            window: 0,
+            pin: None,
+        }
+    }
+
+    /// Enables single click detection on the given axis.
+    #[inline(always)]
+    pub const fn single(mut self, axis: u8) -> Self {
+        if (axis & accel::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 0 }
+        if (axis & accel::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 2 }
+        if (axis & accel::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 4 }
+
+        self
+    }
+
+    /// Enables double click detection on the given axis.
+    #[inline(always)]
+    pub const fn double(mut self, axis: u8) -> Self {
+        if (axis & accel::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 1 }
+        if (axis & accel::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 3 }
+        if (axis & accel::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 5 }
+
+        self
+    }
+
+    /// Sets the click threshold in mg.
+    /// The threshold is converted with the range of the device when applied.
+    #[inline(always)]
+    pub const fn threshold(mut self, mg: u16) -> Self {
+        self.threshold = mg;
+        self
+    }
+
+    /// Sets the maximum time in ms the acceleration can stay above the
+    /// threshold to be detected as a click.
+    #[inline(always)]
+    pub const fn limit(mut self, ms: u16) -> Self {
+        self.limit = ms;
+        self
+    }
+
+    /// Sets the time in ms after the first click during which a new click
+    /// is ignored.
+    #[inline(always)]
+    pub const fn latency(mut self, ms: u16) -> Self {
+        self.latency = ms;
+        self
+    }
+
+    /// Sets the time window in ms after the latency in which the second
+    /// click of a double click must start.
+    #[inline(always)]
+    pub const fn window(mut self, ms: u16) -> Self {
+        self.window = ms;
+        self
+    }
+
+    /// Routes the click interrupt to the given pin.
+    #[inline(always)]
+    pub const fn pin(mut self, pin: Pin) -> Self {
+        self.pin = Some(pin);
+        self
+    }
+
+    /// Returns the Click Threshold, Time Limit, Time Latency and Time Window
+    /// register values for the given range and data rate in Hz.
+    pub(super) fn registers(&self, range: accel::Range, hz: u32) -> [u8; 4] {
+        // Converts a time in ms to a number of samples.
+        let samples = |ms: u16, max: u32| -> u8 {
+            let n = ((ms as u32) * hz) / 1000;
+
+            if n > max { max as u8 } else { n as u8 }
+        };
+
+        let ths = self.threshold / range.threshold();
+
+        [
+            if ths > 0x7F { 0x7F } else { ths as u8 },
+            samples(self.limit,   0x7F),
+            samples(self.latency, 0xFF),
+            samples(self.window,  0xFF),
+        ]
+    }
+}
+
+impl const Default for Click {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickSource {
+    /// One or more click interrupts have been generated.
+    pub active: bool,
+
+    /// Single click detected.
+    pub single: bool,
+
+    /// Double click detected.
+    pub double: bool,
+
+    /// The click was in the negative direction of the axis.
+    pub negative: bool,
+
+    /// Click detected on the X-axis.
+    pub x: bool,
+    /// Click detected on the Y-axis.
+    pub y: bool,
+    /// Click detected on the Z-axis.
+    pub z: bool,
+}
+
+impl core::convert::From<u8> for ClickSource {
+    fn from(s: u8) -> ClickSource {
+        ClickSource {
+            active:   (s & (1 << 6)) != 0,
+            double:   (s & (1 << 5)) != 0,
+            single:   (s & (1 << 4)) != 0,
+            negative: (s & (1 << 3)) != 0,
+
+            z: (s & (1 << 2)) != 0,
+            y: (s & (1 << 1)) != 0,
+            x: (s & (1 << 0)) != 0,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers() {
+        let click = Click::new()
+            .threshold(500)
+            .limit(10)
+            .latency(20)
+            .window(100);
+
+        // 16 mg / LSB, 400 Hz.
+        assert_eq!(click.registers(accel::Range::G2, 400), [31, 4, 8, 40]);
+
+        // 186 mg / LSB, 1344 Hz.
+        assert_eq!(click.registers(accel::Range::G16, 1344), [2, 13, 26, 134]);
+    }
+
+    #[test]
+    fn registers_saturate() {
+        let click = Click::new()
+            .threshold(4000)
+            .limit(1000)
+            .latency(1000)
+            .window(1000);
+
+        assert_eq!(click.registers(accel::Range::G2, 400), [0x7F, 0x7F, 0xFF, 0xFF]);
+        assert_eq!(click.registers(accel::Range::G16, 1), [21, 1, 1, 1]);
+    }
+}
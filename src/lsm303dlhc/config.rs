@@ -4,6 +4,8 @@
 use super::accel;
 use super::mag;
 
+use embedded_hal::i2c::SevenBitAddress;
+
 
 
 #[derive(Debug, Clone, Copy)]
@@ -26,6 +28,9 @@ pub struct Config {
     /// Ctrl6 register.
     pub(super) ctrl6: u8,
 
+    /// FIFO Control register.
+    pub(super) fifoctrl: u8,
+
 
 
     /// Cra register.
@@ -75,7 +80,19 @@ impl Config {
         self
     }
 
+    /// Sets the LSM303DLHC accelerometer mode.
+    #[inline(always)]
+    pub const fn mode(self, mode: accel::Mode) -> Self {
+        match mode {
+            accel::Mode::HighResolution => self.highres(),
+            accel::Mode::Normal => self.normal(),
+            accel::Mode::LowPower => self.normal().lowpower(),
+        }
+    }
+
     /// Sets the accelerator and magnetometer output data rates.
+    /// Setting a magnetometer data rate also selects the continuous conversion
+    /// mode, use `magmode` afterwards to select another mode.
     #[inline(always)]
     pub const fn datarate(mut self, accel: Option<accel::DataRate>, mag: Option<mag::DataRate>) -> Self {
         if let Some(accel) = accel {
@@ -93,6 +110,15 @@ impl Config {
         self
     }
 
+    /// Sets the magnetometer operating mode.
+    #[inline(always)]
+    pub const fn magmode(mut self, mode: mag::OperatingMode) -> Self {
+        self.mr &= 0xFC;
+        self.mr |= mode as u8;
+
+        self
+    }
+
     /// Sets the accelerator and magnetometer scale.
     #[inline(always)]
     pub const fn scale(mut self, accel: Option<accel::Scale>, mag: Option<mag::Scale>) -> Self {
@@ -119,6 +145,79 @@ impl Config {
     }
 
 
+    /// Sets the accelerometer FIFO mode and the watermark level.
+    /// The watermark is a 5 bit value, higher bits are ignored.
+    /// The FIFO is enabled in CTRL5 unless the `Bypass` mode is selected.
+    #[inline(always)]
+    pub const fn fifo(mut self, mode: accel::FIFOMode, watermark: u8) -> Self {
+        self.fifoctrl = ((mode as u8) << 6) | (watermark & 0x1F);
+
+        match mode {
+            accel::FIFOMode::Bypass => self.ctrl5 &= !(1 << 6),
+            _ => self.ctrl5 |= 1 << 6,
+        }
+
+        self
+    }
+
+
+    /// Builds the configuration read back from the CTRL1-6, FIFO Control,
+    /// Cra, Crb and Mr registers.
+    /// Returns `None` if the magnetometer gain is not a valid setting.
+    pub(super) fn from_registers(ctrl: [u8; 6], fifoctrl: u8, cr: [u8; 3]) -> Option<Self> {
+        // GN = 000 is not a documented gain setting.
+        if (cr[1] >> 5) == 0 { return None }
+
+        Some( Config {
+            ctrl1: ctrl[0],
+            ctrl2: ctrl[1],
+            ctrl3: ctrl[2],
+            ctrl4: ctrl[3],
+            ctrl5: ctrl[4],
+            ctrl6: ctrl[5],
+
+            fifoctrl,
+
+            cra: cr[0],
+            crb: cr[1],
+            mr:  cr[2],
+        })
+    }
+
+    /// Returns the device address, register and value of each register in
+    /// the order they are written to the device.
+    /// The magnetometer gain is written before the data rate and mode.
+    pub(super) fn registers(&self) -> [(SevenBitAddress, u8, u8); 10] {
+        [
+            (accel::ACCEL, accel::Register::Ctrl1    as u8, self.ctrl1   ),
+            (accel::ACCEL, accel::Register::Ctrl2    as u8, self.ctrl2   ),
+            (accel::ACCEL, accel::Register::Ctrl3    as u8, self.ctrl3   ),
+            (accel::ACCEL, accel::Register::Ctrl4    as u8, self.ctrl4   ),
+            (accel::ACCEL, accel::Register::Ctrl5    as u8, self.ctrl5   ),
+            (accel::ACCEL, accel::Register::Ctrl6    as u8, self.ctrl6   ),
+            (accel::ACCEL, accel::Register::FIFOCtrl as u8, self.fifoctrl),
+            (mag::MAG,     mag::Register::Crb        as u8, self.crb     ),
+            (mag::MAG,     mag::Register::Cra        as u8, self.cra     ),
+            (mag::MAG,     mag::Register::Mr         as u8, self.mr      ),
+        ]
+    }
+
+    /// Sets the value of the register at the given index of `registers`.
+    pub(super) fn set(&mut self, index: usize, value: u8) {
+        match index {
+            0 => self.ctrl1 = value,
+            1 => self.ctrl2 = value,
+            2 => self.ctrl3 = value,
+            3 => self.ctrl4 = value,
+            4 => self.ctrl5 = value,
+            5 => self.ctrl6 = value,
+            6 => self.fifoctrl = value,
+            7 => self.crb = value,
+            8 => self.cra = value,
+            _ => self.mr = value,
+        }
+    }
+
     /// Returns the parameters.
     pub fn params(&self) -> ((accel::Mode, accel::Range), mag::Range) {
         // Get the accelerometer mode.
@@ -146,6 +245,27 @@ impl Config {
             mrange,
         )
     }
+
+    /// Returns the accelerometer output data rate in Hz.
+    /// Returns 0 if the accelerometer is powered down.
+    pub fn accel_hz(&self) -> u32 {
+        match self.ctrl1 >> 4 {
+            0b0001 =>    1,
+            0b0010 =>   10,
+            0b0011 =>   25,
+            0b0100 =>   50,
+            0b0101 =>  100,
+            0b0110 =>  200,
+            0b0111 =>  400,
+            0b1000 => 1620,
+            0b1001 => match (self.ctrl1 >> 3) & 1 {
+                0 => 1344,
+                _ => 5376,
+            },
+
+            _ => 0,
+        }
+    }
 }
 
 impl const Default for Config {
@@ -158,6 +278,8 @@ impl const Default for Config {
             ctrl5: 0b01000000,
             ctrl6: 0b00000000,
 
+            fifoctrl: 0b00000000,
+
 
 
             cra: 0b10000000,
@@ -166,3 +288,19 @@ impl const Default for Config {
         }
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_registers_rejects_invalid_gain() {
+        assert!( Config::from_registers([0; 6], 0, [0x10, 0x00, 0x03]).is_none() );
+        assert!( Config::from_registers([0; 6], 0, [0x10, 0x1F, 0x03]).is_none() );
+
+        let cfg = Config::from_registers([0; 6], 0, [0x10, 0x20, 0x03]).unwrap();
+        assert_eq!(cfg.crb, 0x20);
+    }
+}
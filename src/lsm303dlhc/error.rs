@@ -8,6 +8,29 @@ pub enum Error<E> {
     RangeOverflowY,
     RangeOverflowZ,
 
+    /// The device on the bus did not identify as an LSM303DLHC.
+    /// Contains the values read from the magnetometer ID registers.
+    WrongDevice { found: [u8; 3] },
+
+    /// The temperature sensor is disabled in the configuration.
+    /// Enable it with `Config::temperature(true)`.
+    TemperatureDisabled,
+
+    /// The configuration read from the device is not valid (e.g. the
+    /// magnetometer gain is set to the undocumented value 000).
+    InvalidConfig,
+
     /// An error ocurred in the underlying bus subsystem.
     BusError(E)
 }
+
+impl<E> Error<E> {
+    /// Returns the range overflow error of the given axis index.
+    pub(super) fn overflow(axis: usize) -> Self {
+        match axis {
+            0 => Error::RangeOverflowX,
+            1 => Error::RangeOverflowY,
+            _ => Error::RangeOverflowZ,
+        }
+    }
+}
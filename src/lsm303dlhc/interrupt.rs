@@ -0,0 +1,244 @@
+//! Inertial interrupt generators configuration for the LSM303DLHC device.
+
+
+use crate::Orientation;
+
+use super::accel;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generator {
+    /// Interrupt generator 1.
+    Int1,
+
+    /// Interrupt generator 2.
+    Int2,
+}
+
+impl Generator {
+    /// Returns the configuration, source, threshold and duration registers.
+    pub(super) fn registers(&self) -> [accel::Register; 4] {
+        match *self {
+            Generator::Int1 => [
+                accel::Register::Int1Cfg, accel::Register::Int1Src,
+                accel::Register::Int1Ths, accel::Register::Int1Dur,
+            ],
+
+            Generator::Int2 => [
+                accel::Register::Int2Cfg, accel::Register::Int2Src,
+                accel::Register::Int2Ths, accel::Register::Int2Dur,
+            ],
+        }
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pin {
+    /// INT1 pin.
+    Int1,
+
+    /// INT2 pin.
+    Int2,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum InterruptMode {
+    /// OR combination of the enabled events.
+    Or = 0b00,
+
+    /// 6 direction movement recognition.
+    /// Triggers when the orientation moves from an unknown zone to a known zone.
+    Movement6D = 0b01,
+
+    /// AND combination of the enabled events.
+    And = 0b10,
+
+    /// 6 direction position recognition.
+    /// Triggers while the orientation is inside a known zone.
+    Position6D = 0b11,
+}
+
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Interrupt {
+    /// Interrupt Configuration register.
+    pub(super) cfg: u8,
+
+    /// Threshold in mg.
+    pub(super) threshold: u16,
+
+    /// Interrupt Duration register.
+    pub(super) duration: u8,
+
+    /// Latch the interrupt until the source is read.
+    pub(super) latch: bool,
+
+    /// Output pin of the interrupt.
+    pub(super) pin: Option<Pin>,
+}
+
+impl Interrupt {
+    /// Static initializer.
+    /// All events are disabled, the events are combined with an OR and the
+    /// interrupt is not routed to any pin.
+    pub const fn new() -> Self {
+        Interrupt {
+            cfg: 0b00000000,
+            threshold: 0,
+            duration: 0b00000000,
+            latch: false,
+            pin: None,
+        }
+    }
+
+    /// Enables the interrupt on the acceleration of the given axis rising
+    /// above the threshold.
+    #[inline(always)]
+    pub const fn high(mut self, axis: u8) -> Self {
+        if (axis & accel::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 1 }
+        if (axis & accel::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 3 }
+        if (axis & accel::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 5 }
+
+        self
+    }
+
+    /// Enables the interrupt on the acceleration of the given axis falling
+    /// below the threshold.
+    #[inline(always)]
+    pub const fn low(mut self, axis: u8) -> Self {
+        if (axis & accel::Axis::XAxis as u8) != 0 { self.cfg |= 1 << 0 }
+        if (axis & accel::Axis::YAxis as u8) != 0 { self.cfg |= 1 << 2 }
+        if (axis & accel::Axis::ZAxis as u8) != 0 { self.cfg |= 1 << 4 }
+
+        self
+    }
+
+    /// Sets how the enabled events are combined.
+    #[inline(always)]
+    pub const fn mode(mut self, mode: InterruptMode) -> Self {
+        self.cfg &= !(0x3 << 6);
+        self.cfg |= (mode as u8) << 6;
+
+        self
+    }
+
+    /// Sets the threshold in mg.
+    /// The threshold is converted with the range of the device when applied.
+    #[inline(always)]
+    pub const fn threshold(mut self, mg: u16) -> Self {
+        self.threshold = mg;
+        self
+    }
+
+    /// Sets the minimum duration of the event in samples (7 bits).
+    /// The duration in seconds is the number of samples divided by the
+    /// accelerometer data rate.
+    #[inline(always)]
+    pub const fn duration(mut self, samples: u8) -> Self {
+        self.duration = samples & 0x7F;
+        self
+    }
+
+    /// Enables / Disables latching the interrupt until the source is read.
+    #[inline(always)]
+    pub const fn latch(mut self, s: bool) -> Self {
+        self.latch = s;
+        self
+    }
+
+    /// Routes the interrupt to the given pin.
+    #[inline(always)]
+    pub const fn pin(mut self, pin: Pin) -> Self {
+        self.pin = Some(pin);
+        self
+    }
+
+    /// Returns a free-fall detector.
+    /// Triggers when the acceleration of all axis stays below the threshold
+    /// for at least the given number of samples.
+    /// The datasheet suggests thresholds around 350 mg.
+    pub const fn freefall(mg: u16, samples: u8) -> Self {
+        Interrupt::new()
+            .low(accel::Axis::XAxis + accel::Axis::YAxis + accel::Axis::ZAxis)
+            .mode(InterruptMode::And)
+            .threshold(mg)
+            .duration(samples)
+    }
+
+    /// Returns the threshold register value for the given range.
+    pub(super) fn ths(&self, range: accel::Range) -> u8 {
+        let raw = self.threshold / range.threshold();
+
+        if raw > 0x7F { 0x7F } else { raw as u8 }
+    }
+}
+
+impl const Default for Interrupt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptSource {
+    /// One or more interrupts have been generated.
+    pub active: bool,
+
+    /// X-axis high event.
+    pub xh: bool,
+    /// X-axis low event.
+    pub xl: bool,
+
+    /// Y-axis high event.
+    pub yh: bool,
+    /// Y-axis low event.
+    pub yl: bool,
+
+    /// Z-axis high event.
+    pub zh: bool,
+    /// Z-axis low event.
+    pub zl: bool,
+}
+
+impl core::convert::From<u8> for InterruptSource {
+    fn from(s: u8) -> InterruptSource {
+        InterruptSource {
+            active: (s & (1 << 6)) != 0,
+
+            xl: (s & (1 << 0)) != 0,
+            xh: (s & (1 << 1)) != 0,
+            yl: (s & (1 << 2)) != 0,
+            yh: (s & (1 << 3)) != 0,
+            zl: (s & (1 << 4)) != 0,
+            zh: (s & (1 << 5)) != 0,
+        }
+    }
+}
+
+impl core::convert::From<InterruptSource> for Orientation {
+    /// Decodes the orientation from the source of a generator configured
+    /// in 6D position mode.
+    fn from(s: InterruptSource) -> Orientation {
+        if !s.active { return Orientation::Unknown }
+
+        match (s.xl, s.xh, s.yl, s.yh, s.zl, s.zh) {
+            (_, _, _, _, _, true) => Orientation::FaceUp,
+            (_, _, _, _, true, _) => Orientation::FaceDown,
+            (_, _, _, true, _, _) => Orientation::PortraitUp,
+            (_, _, true, _, _, _) => Orientation::PortraitDown,
+            (_, true, _, _, _, _) => Orientation::LandscapeRight,
+            (true, _, _, _, _, _) => Orientation::LandscapeLeft,
+
+            _ => Orientation::Unknown,
+        }
+    }
+}
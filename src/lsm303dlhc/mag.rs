@@ -10,6 +10,18 @@ use embedded_hal::i2c::SevenBitAddress;
 /// Magnetometer module I2C address.
 pub(crate) const MAG: SevenBitAddress   = 0b0011110;
 
+/// Expected contents of the ID A, B and C registers.
+pub(crate) const IDENTITY: [u8; 3] = [0x48, 0x34, 0x33];
+
+/// Temperature sensor sensitivity in LSB / ºC.
+pub(crate) const TEMP_LSB: f32 = 8.0;
+
+/// Temperature sensor resolution in mºC / LSB.
+pub(crate) const TEMP_MC: i32 = 125;
+
+/// Default temperature in ºC that corresponds to a raw output of 0.
+pub(crate) const TEMP_OFFSET: f32 = 20.0;
+
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -39,6 +51,19 @@ pub enum DataRate {
     Hz220  = 0b111,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OperatingMode {
+    /// Continuous conversion at the selected data rate.
+    Continuous = 0b00,
+
+    /// Single conversion. The magnetometer sleeps after the conversion.
+    Single = 0b01,
+
+    /// Sleep mode.
+    Sleep = 0b11,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Range {
@@ -141,3 +166,16 @@ pub(crate) enum Register {
     /// Temperature LSB output.
     TempOutL = 0x32,
 }
+
+
+
+/// Decodes a big endian sample in the X, Z and Y order of the output
+/// registers into an X, Y and Z sample.
+#[inline(always)]
+pub(super) fn decode(data: &[u8]) -> [i16; 3] {
+    [
+        i16::from_be_bytes([data[0], data[1]]),
+        i16::from_be_bytes([data[4], data[5]]),
+        i16::from_be_bytes([data[2], data[3]]),
+    ]
+}
@@ -1,24 +1,35 @@
 //! LSM303DLHC accelerometer, magnetometer and thermometer device.
+//! The datasheet does not document self-test bits in CTRL_REG4_A (bits 2:1
+//! must be written as 0), so this driver has no self-test routine.
 
 
 pub mod accel;
 pub mod mag;
 
+mod click;
 mod config;
 mod error;
+mod interrupt;
 
+#[cfg(feature = "async")]
+mod asynch;
 
+
+pub use self::click::{ Click, ClickSource };
 pub use self::config::Config;
 pub use self::error::Error;
+pub use self::interrupt::{ Generator, Interrupt, InterruptMode, InterruptSource, Pin };
+
+#[cfg(feature = "async")]
+pub use self::asynch::Lsm303dlhcAsync;
 
-use crate::{ Accelerometer, Magnetometer, Thermometer };
+use crate::{ Accelerometer, Magnetometer, Orientation, Reading, Saturation, Thermometer };
+use crate::units::{ self, Celsius, MetersPerSecondSquared, MicroTesla, MilliCelsius, MilliG, NanoTesla, Vector3 };
 
-use core::{ mem::MaybeUninit, ops::* };
+use core::ops::*;
 
-use embedded_hal::i2c::SevenBitAddress;
-use embedded_hal::i2c::blocking::{
-    Write, WriteRead
-};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ I2c, SevenBitAddress };
 
 
 
@@ -32,236 +43,922 @@ pub struct Lsm303dlhc<I> {
 
     /// Magnetometer range.
     mag: mag::Range,
-}
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Lsm303dlhc<I> {
+    /// Last configuration applied to the device.
+    cfg: Config,
 
-    /// Creates a new driver and configures the device.
-    pub fn create(interface: I, cfg: Config) -> Result<Self, <I as Write>::Error> {
-        // Get the parameters of the accelerometer and magnetometer.
+    /// Saturation policy.
+    saturation: Saturation,
+
+    /// Saturation flags of the last normalized accelerometer and
+    /// magnetometer samples.
+    saturated: ([bool; 3], [bool; 3]),
+
+    /// Temperature in ºC that corresponds to a raw output of 0.
+    temp_offset: f32,
+
+    /// Temperature offset rounded to mºC for the fixed point output.
+    temp_offset_mc: i32,
+}
+
+impl<I> Lsm303dlhc<I> {
+    /// Creates the driver state without accessing the device.
+    fn build(interface: I, cfg: Config) -> Self {
         let (accel, mag) = cfg.params();
 
+        Lsm303dlhc {
+            interface, accel, mag, cfg,
+            saturation: Saturation::Error,
+            saturated: ([false; 3], [false; 3]),
+            temp_offset: mag::TEMP_OFFSET,
+            temp_offset_mc: units::round(mag::TEMP_OFFSET * 1000.0),
+        }
+    }
+
+    /// Returns the last configuration applied to the device.
+    pub fn config(&self) -> Config {
+        self.cfg
+    }
+
+    /// Sets the policy applied to saturated axis in the normalized
+    /// accelerometer and magnetometer outputs.
+    pub fn set_saturation(&mut self, policy: Saturation) {
+        self.saturation = policy;
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized
+    /// accelerometer sample.
+    pub fn accel_saturated(&self) -> [bool; 3] {
+        self.saturated.0
+    }
+
+    /// Returns the per-axis saturation flags of the last normalized
+    /// magnetometer sample.
+    pub fn mag_saturated(&self) -> [bool; 3] {
+        self.saturated.1
+    }
+
+    /// Sets the temperature in ºC that corresponds to a raw output of 0.
+    /// Defaults to 20 ºC.
+    pub fn set_temperature_offset(&mut self, offset: f32) {
+        self.temp_offset = offset;
+        self.temp_offset_mc = units::round(offset * 1000.0);
+    }
+
+    /// Returns the temperature offset in ºC.
+    pub fn temperature_offset(&self) -> f32 {
+        self.temp_offset
+    }
+
+    /// Stores the temperature offset that makes the raw output equal to the
+    /// known temperature in ºC.
+    fn calibrate(&mut self, raw: i16, known: f32) {
+        self.temp_offset = known - (raw as f32 / mag::TEMP_LSB);
+        self.temp_offset_mc = units::round(known * 1000.0) - (raw as i32 * mag::TEMP_MC);
+    }
+
+    /// Drops the unused low bits of the left aligned accelerometer data and
+    /// applies the saturation policy at the resolution of the current mode.
+    fn accel_saturate<E>(&mut self, raw: [i16; 3]) -> Result<[i16; 3], Error<E>> {
+        let mut raw = self.accel_shift(raw);
+
+        let max = self.accel.0.limit();
+
+        match self.saturation.apply(&mut raw, -max, max - 1) {
+            Err(axis) => return Err( Error::overflow(axis) ),
+            Ok(flags) => self.saturated.0 = flags,
+        };
+
+        Ok(raw)
+    }
+
+    /// Drops the unused low bits of the left aligned accelerometer data.
+    fn accel_shift(&self, raw: [i16; 3]) -> [i16; 3] {
+        let shift = self.accel.0.shift();
+
+        [raw[0] >> shift, raw[1] >> shift, raw[2] >> shift]
+    }
+
+    /// Applies the saturation policy to the raw magnetometer data.
+    /// An overflowed axis reads -4096, so a clamped axis loses its sign.
+    fn mag_saturate<E>(&mut self, raw: [i16; 3]) -> Result<[i16; 3], Error<E>> {
+        let mut raw = raw;
+
+        match self.saturation.apply(&mut raw, -2048, 2047) {
+            Err(axis) => return Err( Error::overflow(axis) ),
+            Ok(flags) => self.saturated.1 = flags,
+        };
+
+        Ok(raw)
+    }
+
+    /// Applies the saturation policy and converts the raw accelerometer data
+    /// to m/s^2.
+    fn accel_normalize<E, F>(&mut self, raw: [i16; 3]) -> Result<Vector3<MetersPerSecondSquared<F>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        // Check for saturation at the resolution of the current mode.
+        let raw = self.accel_saturate(raw)?;
+
+        Ok( self.accel_convert(raw) )
+    }
+
+    /// Converts the shifted accelerometer data to m/s^2.
+    fn accel_convert<F>(&self, raw: [i16; 3]) -> Vector3<MetersPerSecondSquared<F>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        // Get the LSB data.
+        let (_, lsb) = self.accel.0.params(self.accel.1);
+
+        let [rawx, rawy, rawz] = raw;
+
+        // Calculate the acceleration.
+        let accx = F::from(rawx) * F::from(lsb) * F::from( 9.80665 );
+        let accy = F::from(rawy) * F::from(lsb) * F::from( 9.80665 );
+        let accz = F::from(rawz) * F::from(lsb) * F::from( 9.80665 );
+
+        Vector3::new(MetersPerSecondSquared(accx), MetersPerSecondSquared(accy), MetersPerSecondSquared(accz))
+    }
+
+    /// Applies the saturation policy and converts the raw magnetometer data
+    /// to gauss * 100 (uT).
+    fn mag_normalize<E, F>(&mut self, raw: [i16; 3]) -> Result<Vector3<MicroTesla<F>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        // Check for a range overflow.
+        let [rawx, rawy, rawz] = self.mag_saturate(raw)?;
+
+        // Get the resolution multiplier.
+        let (xy, z) = self.mag.params();
+
+        let magx = (F::from(rawx) / F::from(xy)) * F::from(100);
+        let magy = (F::from(rawy) / F::from(xy)) * F::from(100);
+        let magz = (F::from(rawz) / F::from( z)) * F::from(100);
+
+        Ok( Vector3::new(MicroTesla(magx), MicroTesla(magy), MicroTesla(magz)) )
+    }
+
+    /// Applies the saturation policy and converts the raw accelerometer data
+    /// to mg with integer arithmetic.
+    fn accel_fixed<E>(&mut self, raw: [i16; 3]) -> Result<Vector3<MilliG>, Error<E>> {
+        let raw = self.accel_saturate(raw)?;
+
+        // Get the resolution in ug / LSB.
+        let ug = self.accel.0.ug(self.accel.1);
+
+        Ok( Vector3::from(raw).map(|v| MilliG( units::scale(v as i32, ug, 1000) )) )
+    }
+
+    /// Applies the saturation policy and converts the raw magnetometer data
+    /// to nT with integer arithmetic.
+    fn mag_fixed<E>(&mut self, raw: [i16; 3]) -> Result<Vector3<NanoTesla>, Error<E>> {
+        let [rawx, rawy, rawz] = self.mag_saturate(raw)?;
+
+        // Get the resolution in LSB / gauss, 1 gauss = 100000 nT.
+        let (xy, z) = self.mag.params();
+
+        Ok( Vector3::new(
+            NanoTesla( units::scale(rawx as i32, 100_000, xy as i32) ),
+            NanoTesla( units::scale(rawy as i32, 100_000, xy as i32) ),
+            NanoTesla( units::scale(rawz as i32, 100_000,  z as i32) ),
+        ))
+    }
+
+    /// Checks that the temperature sensor is enabled in the configuration.
+    fn temperature_enabled<E>(&self) -> Result<(), Error<E>> {
+        match self.cfg.cra & 0x80 {
+            0 => Err( Error::TemperatureDisabled ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts the raw temperature data to ºC.
+    /// The output is relative to the calibration offset, 8 LSB / ºC.
+    fn celsius<F>(&self, raw: i16) -> Celsius<F>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        Celsius( F::from(self.temp_offset) + (F::from(raw) / F::from(mag::TEMP_LSB)) )
+    }
+
+    /// Converts the raw temperature data to mºC.
+    /// The output is relative to the calibration offset, 8 LSB / ºC.
+    fn milli_celsius(&self, raw: i16) -> MilliCelsius {
+        MilliCelsius( self.temp_offset_mc + (raw as i32 * mag::TEMP_MC) )
+    }
+}
+
+
+
+impl<E, I: I2c<SevenBitAddress, Error=E>> Lsm303dlhc<I> {
+
+    /// Creates a new driver and configures the device.
+    /// Fails with `Error::WrongDevice` if the magnetometer does not identify
+    /// as an LSM303DLHC.
+    pub fn create(interface: I, cfg: Config) -> Result<Self, Error<E>> {
         // Create the device.
-        let mut device = Lsm303dlhc { interface, accel, mag };
+        let mut device = Lsm303dlhc::build(interface, cfg);
 
-        // Configure accelrometer module.
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl1 as u8, cfg.ctrl1])?;
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl2 as u8, cfg.ctrl2])?;
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl3 as u8, cfg.ctrl3])?;
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl4 as u8, cfg.ctrl4])?;
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl5 as u8, cfg.ctrl5])?;
-        device.wr(accel::ACCEL, &[accel::Register::Ctrl6 as u8, cfg.ctrl6])?;
+        // Check the identity of the device.
+        device.identify()?;
 
+        // Configure the device.
+        match device.configure() {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
 
-        // Reset the magnetometer gain.
-        device.wr(mag::MAG, &[mag::Register::Crb as u8, 0x00])?;
-        // Configure magnetometer gain.
-        device.wr(mag::MAG, &[mag::Register::Crb as u8, cfg.crb])?;
-        // Set output data rate and temperature.
-        device.wr(mag::MAG, &[mag::Register::Cra as u8, cfg.cra])?;
-        // Enable continous mode, single conversion or sleep mode.
-        device.wr(mag::MAG, &[mag::Register::Mr  as u8, cfg.mr ])?;
+        Ok(device)
+    }
+
+    /// Creates a new driver for an already configured device.
+    /// The configuration is read back from the device instead of written.
+    /// Fails with `Error::WrongDevice` if the magnetometer does not identify
+    /// as an LSM303DLHC and with `Error::InvalidConfig` if the registers
+    /// do not hold a valid configuration.
+    pub fn attach(interface: I) -> Result<Self, Error<E>> {
+        // Create the device with a placeholder configuration.
+        let mut device = Lsm303dlhc::build(interface, Config::default());
+
+        // Check the identity of the device.
+        device.identify()?;
+
+        // Adopt the configuration of the device.
+        device.cfg = device.read_config()?;
+        (device.accel, device.mag) = device.cfg.params();
 
         Ok(device)
     }
 
+    /// Reads the current configuration from the device registers.
+    /// Fails with `Error::InvalidConfig` if the magnetometer gain is not a
+    /// valid setting.
+    pub fn read_config(&mut self) -> Result<Config, Error<E>> {
+        let mut ctrl = [0u8; 6];
+        let mut fifo = [0u8; 1];
+        let mut cr   = [0u8; 3];
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::Ctrl1 as u8 | (1 << 7)], &mut ctrl) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::FIFOCtrl as u8], &mut fifo) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match self.wrrd(mag::MAG, &[mag::Register::Cra as u8 | (1 << 7)], &mut cr) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        match Config::from_registers(ctrl, fifo[0], cr) {
+            Some(cfg) => Ok(cfg),
+            None => Err( Error::InvalidConfig ),
+        }
+    }
+
+    /// Reads the magnetometer ID registers and checks them against the
+    /// expected identity.
+    fn identify(&mut self) -> Result<(), Error<E>> {
+        let mut data = [0u8; 3];
+
+        match self.wrrd(mag::MAG, &[mag::Register::IdA as u8 | (1 << 7)], &mut data) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        if data != mag::IDENTITY {
+            return Err( Error::WrongDevice { found: data } )
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last applied configuration to the device.
+    fn configure(&mut self) -> Result<(), E> {
+        // Reset the magnetometer gain.
+        self.wr(mag::MAG, &[mag::Register::Crb as u8, 0x00])?;
+
+        // Configure the accelerometer module, its FIFO and the magnetometer
+        // gain, data rate, temperature and mode.
+        for (addr, reg, value) in self.cfg.registers() {
+            self.wr(addr, &[reg, value])?;
+        }
+
+        Ok(())
+    }
+
+    /// One-point calibration of the temperature sensor.
+    /// Reads the current output and stores the offset that makes it equal
+    /// to the given known temperature in ºC.
+    pub fn calibrate_temperature(&mut self, known: f32) -> Result<(), Error<E>> {
+        let raw = self.tempraw()?;
+
+        self.calibrate(raw, known);
+
+        Ok(())
+    }
+
     /// Sleeps the device.
-    /// To wake it up, the user must select again the output data rate
-    /// or reset the device.
-    pub fn sleep(&mut self) -> Result<(), <I as Write>::Error> {
+    /// To wake it up, the user must select again the output data rate,
+    /// call `wake` or reset the device.
+    pub fn sleep(&mut self) -> Result<(), E> {
         // Configure accelrometer module.
         self.wr(accel::ACCEL, &[accel::Register::Ctrl1 as u8, 0b00001000])?;
 
-        self.wr(mag::MAG, &[mag::Register::Mr  as u8, 0b00000011 ])?;
+        self.wr(mag::MAG, &[mag::Register::Mr  as u8, mag::OperatingMode::Sleep as u8])?;
 
         Ok(())
     }
-}
 
+    /// Wakes the device up from sleep with the last applied configuration.
+    pub fn wake(&mut self) -> Result<(), E> {
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl1 as u8, self.cfg.ctrl1])?;
+        self.wr(mag::MAG, &[mag::Register::Mr  as u8, self.cfg.mr])?;
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Accelerometer for Lsm303dlhc<I> {
-    type Error = Error<<I as WriteRead>::Error>;
-    type Output = i16;
+        Ok(())
+    }
 
-    fn accelraw(&mut self) -> Result<[i16; 3], Error<<I as WriteRead>::Error>> {
-        // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+    /// Applies a new configuration to the device.
+    /// Only the registers that differ from the last applied configuration
+    /// are written. The interrupt and click thresholds are not rescaled if
+    /// the range changes.
+    pub fn apply(&mut self, cfg: Config) -> Result<(), E> {
+        let new = cfg.registers();
 
-        // Read in the output data.
-        self.wrrd(accel::ACCEL, &[accel::Register::OutXL as u8 | (1 << 7)], &mut data).unwrap();
+        for (i, (addr, reg, old)) in self.cfg.registers().into_iter().enumerate() {
+            if old == new[i].2 { continue }
+
+            self.wr(addr, &[reg, new[i].2])?;
+
+            // Keep the cache in sync with the registers already written.
+            self.cfg.set(i, new[i].2);
+            (self.accel, self.mag) = self.cfg.params();
+        }
+
+        Ok(())
+    }
+
+    /// Reboots the accelerometer memory content, reloading the trimming
+    /// parameters, waits for the boot to end and restores the last applied
+    /// configuration.
+    /// The interrupt generators and the click detection are not restored.
+    pub fn reboot<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), E> {
+        // Set the BOOT bit.
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl5 as u8, self.cfg.ctrl5 | (1 << 7)])?;
+
+        // Wait for the boot procedure.
+        delay.delay_us(accel::BOOT_TIME);
+
+        // Restore the configuration.
+        self.configure()
+    }
+
+    /// Changes the accelerometer and magnetometer scales.
+    pub fn set_scale(&mut self, accel: Option<accel::Scale>, mag: Option<mag::Scale>) -> Result<(), E> {
+        self.apply( self.cfg.scale(accel, mag) )
+    }
+
+    /// Changes the accelerometer and magnetometer output data rates.
+    /// Setting a magnetometer data rate also selects the continuous
+    /// conversion mode.
+    pub fn set_datarate(&mut self, accel: Option<accel::DataRate>, mag: Option<mag::DataRate>) -> Result<(), E> {
+        self.apply( self.cfg.datarate(accel, mag) )
+    }
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( (data[0] as u16) | ((data[1] as u16) << 8) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( (data[2] as u16) | ((data[3] as u16) << 8) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( (data[4] as u16) | ((data[5] as u16) << 8) ) };
+    /// Changes the accelerometer mode.
+    pub fn set_mode(&mut self, mode: accel::Mode) -> Result<(), E> {
+        self.apply( self.cfg.mode(mode) )
+    }
 
-        Ok([rawx, rawy, rawz])
+    /// Changes the magnetometer operating mode.
+    pub fn set_magmode(&mut self, mode: mag::OperatingMode) -> Result<(), E> {
+        self.apply( self.cfg.magmode(mode) )
+    }
 
+    /// Starts a single magnetometer conversion.
+    /// The magnetometer goes back to sleep once the conversion is done.
+    /// Use `read_when_ready` to get the result.
+    pub fn trigger_single(&mut self) -> Result<(), E> {
+        self.wr(mag::MAG, &[mag::Register::Mr  as u8, mag::OperatingMode::Single as u8])
     }
 
-    fn accel<F>(&mut self) -> Result<[F; 3], Error<<I as WriteRead>::Error>>
+    /// Reads the normalized magnetometer data once a conversion is done.
+    /// Returns `WouldBlock` while the conversion is in progress.
+    pub fn read_when_ready<F>(&mut self) -> nb::Result<Vector3<MicroTesla<F>>, Error<E>>
         where F: Clone + Copy +
-            From<f32> + From<Self::Output> +
+            From<f32> + From<i16> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>
     {
-        // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+        Ok( self.try_mag()?.data )
+    }
 
-        // Read in the output data.
-        self.wrrd(accel::ACCEL, &[accel::Register::OutXL as u8 | (1 << 7)], &mut data).unwrap();
+    /// Reads the accelerometer raw data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read.
+    pub fn try_accelraw(&mut self) -> nb::Result<Reading<[i16; 3]>, Error<E>> {
+        // Read in the status and the output data in one burst.
+        let mut data = [0u8; 7];
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( (data[0] as u16) | ((data[1] as u16) << 8) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( (data[2] as u16) | ((data[3] as u16) << 8) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( (data[4] as u16) | ((data[5] as u16) << 8) ) };
+        match self.wrrd(accel::ACCEL, &[accel::Register::Status as u8 | (1 << 7)], &mut data) {
+            Err(e) => return Err( nb::Error::Other( Error::BusError(e) ) ),
+            _ => (),
+        };
 
-        // Get the shift and LSB data.
-        let (shift, lsb) = self.accel.0.params(self.accel.1);
+        // Check for new data available (ZYXDA).
+        if (data[0] & (1 << 3)) == 0 { return Err( nb::Error::WouldBlock ) }
 
-        // Calculate the acceleration.
-        let accx = F::from(rawx >> shift) * F::from(lsb) * F::from( 9.80665 );
-        let accy = F::from(rawy >> shift) * F::from(lsb) * F::from( 9.80665 );
-        let accz = F::from(rawz >> shift) * F::from(lsb) * F::from( 9.80665 );
+        Ok( Reading { data: accel::decode(&data[1..]), overrun: (data[0] & (1 << 7)) != 0 } )
+    }
 
-        Ok([accx, accy, accz])
+    /// Reads the normalized accelerometer data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read.
+    pub fn try_accel<F>(&mut self) -> nb::Result<Reading<Vector3<MetersPerSecondSquared<F>>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let Reading { data, overrun } = self.try_accelraw()?;
+
+        Ok( Reading { data: self.accel_normalize(data)?, overrun } )
     }
-}
 
+    /// Reads the magnetometer raw data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read. The magnetometer does not report overruns.
+    pub fn try_magraw(&mut self) -> nb::Result<Reading<[i16; 3]>, Error<E>> {
+        let mut status = [0u8; 1];
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Magnetometer for Lsm303dlhc<I> {
-    type Error = Error<<I as WriteRead>::Error>;
-    type Output = i16;
+        match self.wrrd(mag::MAG, &[mag::Register::Status as u8], &mut status) {
+            Err(e) => return Err( nb::Error::Other( Error::BusError(e) ) ),
+            _ => (),
+        };
+
+        // Check for new data available (DRDY).
+        if (status[0] & 1) == 0 {
+            // The output registers stay locked (LOCK) after a partial read.
+            // Read the stale sample to unlock them for the next conversion.
+            if (status[0] & (1 << 1)) != 0 { self.magraw()?; }
+
+            return Err( nb::Error::WouldBlock )
+        }
+
+        Ok( Reading { data: self.magraw()?, overrun: false } )
+    }
+
+    /// Reads the normalized magnetometer data if a new sample is available.
+    /// Returns `WouldBlock` if no new sample has been generated since the
+    /// last read. The magnetometer does not report overruns.
+    pub fn try_mag<F>(&mut self) -> nb::Result<Reading<Vector3<MicroTesla<F>>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<i16> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let Reading { data, overrun } = self.try_magraw()?;
+
+        Ok( Reading { data: self.mag_normalize(data)?, overrun } )
+    }
+
+    /// Reads the accelerometer FIFO status.
+    pub fn fifo_status(&mut self) -> Result<accel::FIFOStatus, Error<E>> {
+        let mut data = [0u8; 1];
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::FIFOSrc as u8], &mut data) {
+            Err(e) => return Err( Error::BusError(e) ),
+            _ => (),
+        };
+
+        Ok( accel::FIFOStatus::from(data[0]) )
+    }
+
+    /// Drains the accelerometer FIFO into the given buffer with a single
+    /// burst read. Returns the number of samples read. If the buffer is
+    /// smaller than the FIFO level, the remaining samples are kept in the FIFO.
+    pub fn fifo_readraw(&mut self, buffer: &mut [[i16; 3]]) -> Result<usize, Error<E>> {
+        // Get the amount of samples to read.
+        let n = core::cmp::min(self.fifo_status()?.level, buffer.len());
+
+        if n == 0 { return Ok(0) }
 
-    fn magraw(&mut self) -> Result<[i16; 3], Error<<I as WriteRead>::Error>> {
         // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut data = [0u8; 6 * accel::FIFO_DEPTH];
 
-        // Read in the output data.
-        match self.wrrd(mag::MAG, &[mag::Register::OutXH as u8 | (1 << 7)], &mut data) {
+        // With the FIFO enabled the address rolls back from OutZH to OutXL,
+        // so all samples can be read in one auto-increment transaction.
+        match self.wrrd(accel::ACCEL, &[accel::Register::OutXL as u8 | (1 << 7)], &mut data[..6 * n]) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( ((data[0] as u16) << 8) | (data[1] as u16) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( ((data[4] as u16) << 8) | (data[5] as u16) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( ((data[2] as u16) << 8) | (data[3] as u16) ) };
+        for (sample, raw) in buffer.iter_mut().zip(data[..6 * n].chunks_exact(6)) {
+            *sample = accel::decode(raw);
+        }
 
-        Ok([rawx, rawy, rawz])
+        Ok(n)
     }
 
-    fn mag<F>(&mut self) -> Result<[F; 3], Error<<I as WriteRead>::Error>>
+    /// Drains the accelerometer FIFO into the given buffer with a single
+    /// burst read and normalizes the samples with the current mode and range.
+    /// Returns the number of samples read and writes the per-axis saturation
+    /// flags of each sample to `saturated`, up to its length.
+    /// A single sample cannot be discarded from a drained burst, so with the
+    /// `Error` policy saturated samples are returned as read and flagged,
+    /// as with the `Flag` policy.
+    pub fn fifo_read<F>(&mut self, buffer: &mut [Vector3<MetersPerSecondSquared<F>>], saturated: &mut [[bool; 3]]) -> Result<usize, Error<E>>
         where F: Clone + Copy +
-            From<f32> + From<Self::Output> +
+            From<f32> + From<i16> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>
     {
-        // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 6] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut raw = [[0i16; 3]; accel::FIFO_DEPTH];
 
-        // Read in the output data.
-        match self.wrrd(mag::MAG, &[mag::Register::OutXH as u8 | (1 << 7)], &mut data) {
+        let max = core::cmp::min(buffer.len(), accel::FIFO_DEPTH);
+        let n = self.fifo_readraw(&mut raw[..max])?;
+
+        let policy = match self.saturation {
+            Saturation::Error => Saturation::Flag,
+            policy => policy,
+        };
+
+        let limit = self.accel.0.limit();
+
+        for (i, raw) in raw[..n].iter().enumerate() {
+            let mut raw = self.accel_shift(*raw);
+
+            // Neither `Flag` nor `Clamp` can fail.
+            let flags = policy.apply(&mut raw, -limit, limit - 1).unwrap_or([true; 3]);
+
+            buffer[i] = self.accel_convert(raw);
+
+            if let Some(s) = saturated.get_mut(i) { *s = flags }
+        }
+
+        Ok(n)
+    }
+
+    /// Configures the given inertial interrupt generator and routes it to the
+    /// selected pin.
+    pub fn interrupt(&mut self, generator: Generator, int: Interrupt) -> Result<(), E> {
+        let [cfg, _, ths, dur] = generator.registers();
+
+        // Get the latch and routing bits of the generator.
+        let (lir, int1, int2) = match generator {
+            Generator::Int1 => (1 << 3, 1 << 6, 1 << 6),
+            Generator::Int2 => (1 << 1, 1 << 5, 1 << 5),
+        };
+
+        // Configure the interrupt generator.
+        self.wr(accel::ACCEL, &[cfg as u8, int.cfg])?;
+        self.wr(accel::ACCEL, &[ths as u8, int.ths(self.accel.1)])?;
+        self.wr(accel::ACCEL, &[dur as u8, int.duration])?;
+
+        // Latch the interrupt request.
+        if int.latch { self.cfg.ctrl5 |= lir }
+        else { self.cfg.ctrl5 &= !lir }
+
+        // Route the interrupt to the pins.
+        self.cfg.ctrl3 &= !int1;
+        self.cfg.ctrl6 &= !int2;
+
+        match int.pin {
+            Some(Pin::Int1) => self.cfg.ctrl3 |= int1,
+            Some(Pin::Int2) => self.cfg.ctrl6 |= int2,
+            None => (),
+        }
+
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl3 as u8, self.cfg.ctrl3])?;
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl5 as u8, self.cfg.ctrl5])?;
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl6 as u8, self.cfg.ctrl6])?;
+
+        Ok(())
+    }
+
+    /// Reads and decodes the source of the given interrupt generator.
+    /// If the interrupt is latched, reading the source clears it.
+    pub fn interrupt_source(&mut self, generator: Generator) -> Result<InterruptSource, Error<E>> {
+        let [_, src, _, _] = generator.registers();
+
+        let mut data = [0u8; 1];
+
+        match self.wrrd(accel::ACCEL, &[src as u8], &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let rawx: i16 = unsafe { core::mem::transmute( ((data[0] as u16) << 8) | (data[1] as u16) ) };
-        let rawy: i16 = unsafe { core::mem::transmute( ((data[4] as u16) << 8) | (data[5] as u16) ) };
-        let rawz: i16 = unsafe { core::mem::transmute( ((data[2] as u16) << 8) | (data[3] as u16) ) };
+        Ok( InterruptSource::from(data[0]) )
+    }
 
-        // Check for a range overflow.
-        if (rawx >= 2048) || (rawx < -2048) { return Err( Error::RangeOverflowX ) }
-        if (rawy >= 2048) || (rawy < -2048) { return Err( Error::RangeOverflowY ) }
-        //if (rawz >= 2048) || (rawz < -2048) { return Err( Error::RangeOverflowZ ) }
+    /// Configures the INT1 generator as a free-fall detector routed to the
+    /// INT1 pin. The event is latched until read with `freefall_detected`.
+    /// The threshold is given in mg and the minimum duration of the fall
+    /// in ms, converted with the current accelerometer data rate.
+    pub fn freefall(&mut self, mg: u16, ms: u16) -> Result<(), E> {
+        // Get the minimum duration in samples.
+        let samples = ((ms as u32) * self.cfg.accel_hz()) / 1000;
+        let samples = if samples > 0x7F { 0x7F } else { samples as u8 };
 
-        // Get the resolution multiplier.
-        let (xy, z) = self.mag.params();
+        let int = Interrupt::freefall(mg, samples)
+            .latch(true)
+            .pin(Pin::Int1);
 
-        let magx = (F::from(rawx) / F::from(xy)) * F::from(100);
-        let magy = (F::from(rawy) / F::from(xy)) * F::from(100);
-        let magz = (F::from(rawz) / F::from( z)) * F::from(100);
+        self.interrupt(Generator::Int1, int)
+    }
 
-        Ok([magx, magy, magz])
+    /// Returns `true` if a free-fall event was detected since the last read.
+    pub fn freefall_detected(&mut self) -> Result<bool, Error<E>> {
+        Ok( self.interrupt_source(Generator::Int1)?.active )
+    }
+
+    /// Configures the INT1 generator for hardware orientation recognition
+    /// (6D position mode) routed to the INT1 pin. In 4D mode the Z-axis is
+    /// ignored and only the portrait and landscape orientations are recognized.
+    /// The threshold is given in mg.
+    pub fn orientation_detection(&mut self, fourd: bool, mg: u16) -> Result<(), E> {
+        // Enable or disable the 4D detection on INT1.
+        if fourd { self.cfg.ctrl5 |= 1 << 2 }
+        else { self.cfg.ctrl5 &= !(1 << 2) }
+
+        let axis = accel::Axis::XAxis + accel::Axis::YAxis + accel::Axis::ZAxis;
+
+        let int = Interrupt::new()
+            .high(axis)
+            .low(axis)
+            .mode(InterruptMode::Position6D)
+            .threshold(mg)
+            .pin(Pin::Int1);
+
+        self.interrupt(Generator::Int1, int)
     }
-}
 
+    /// Reads the current orientation recognized by the INT1 generator.
+    /// Requires `orientation_detection` to have been configured.
+    pub fn orientation(&mut self) -> Result<Orientation, Error<E>> {
+        Ok( Orientation::from( self.interrupt_source(Generator::Int1)? ) )
+    }
 
-impl<I: Write<SevenBitAddress> + WriteRead<SevenBitAddress>> Thermometer for Lsm303dlhc<I> {
-    type Error = Error<<I as WriteRead>::Error>;
-    type Output = i16;
+    /// Configures the click detection.
+    /// The times are converted with the current accelerometer data rate.
+    pub fn click(&mut self, click: Click) -> Result<(), E> {
+        let [ths, limit, latency, window] = click.registers(self.accel.1, self.cfg.accel_hz());
 
-    fn tempraw(&mut self) -> Result<i16, Error<<I as WriteRead>::Error>> {
-        // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 2] = unsafe { MaybeUninit::uninit().assume_init() };
+        // Configure the click detection.
+        self.wr(accel::ACCEL, &[accel::Register::ClickCfg    as u8, click.cfg])?;
+        self.wr(accel::ACCEL, &[accel::Register::ClickThs    as u8, ths      ])?;
+        self.wr(accel::ACCEL, &[accel::Register::TimeLimit   as u8, limit    ])?;
+        self.wr(accel::ACCEL, &[accel::Register::TimeLatency as u8, latency  ])?;
+        self.wr(accel::ACCEL, &[accel::Register::TimeWindow  as u8, window   ])?;
 
-        // Read in the output data.
-        match self.wrrd(mag::MAG, &[mag::Register::TempOutH as u8], &mut data[0..1]) {
+        // Route the interrupt to the pins.
+        self.cfg.ctrl3 &= !(1 << 7);
+        self.cfg.ctrl6 &= !(1 << 7);
+
+        match click.pin {
+            Some(Pin::Int1) => self.cfg.ctrl3 |= 1 << 7,
+            Some(Pin::Int2) => self.cfg.ctrl6 |= 1 << 7,
+            None => (),
+        }
+
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl3 as u8, self.cfg.ctrl3])?;
+        self.wr(accel::ACCEL, &[accel::Register::Ctrl6 as u8, self.cfg.ctrl6])?;
+
+        Ok(())
+    }
+
+    /// Reads and decodes the click source.
+    pub fn click_source(&mut self) -> Result<ClickSource, Error<E>> {
+        let mut data = [0u8; 1];
+
+        match self.wrrd(accel::ACCEL, &[accel::Register::ClickSrc as u8], &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        match self.wrrd(mag::MAG, &[mag::Register::TempOutL as u8], &mut data[1..]) {
+        Ok( ClickSource::from(data[0]) )
+    }
+}
+
+
+impl<E, I: I2c<SevenBitAddress, Error=E>> Accelerometer for Lsm303dlhc<I> {
+    type Error = Error<E>;
+    type Output = i16;
+
+    fn accelraw(&mut self) -> Result<[i16; 3], Error<E>> {
+        // Create the input buffer.
+        let mut data = [0u8; 6];
+
+        // Read in the output data.
+        match self.wrrd(accel::ACCEL, &[accel::Register::OutXL as u8 | (1 << 7)], &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let raw = (((data[0] as i16) << 8) | (data[1] as i16)) >> 4;
-
-        Ok(raw)
+        Ok( accel::decode(&data) )
     }
 
-    fn temp<F>(&mut self) -> Result<F, Error<<I as WriteRead>::Error>>
+    fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, Error<E>>
         where F: Clone + Copy +
             From<f32> + From<Self::Output> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>
     {
+        let raw = self.accelraw()?;
+
+        self.accel_normalize(raw)
+    }
+
+    fn accel_mg(&mut self) -> Result<Vector3<MilliG>, Error<E>> {
+        let raw = self.accelraw()?;
+
+        self.accel_fixed(raw)
+    }
+}
+
+
+impl<E, I: I2c<SevenBitAddress, Error=E>> Magnetometer for Lsm303dlhc<I> {
+    type Error = Error<E>;
+    type Output = i16;
+
+    fn magraw(&mut self) -> Result<[i16; 3], Error<E>> {
         // Create the input buffer.
-        // This is safe, as this buffer will be written to before we read.
-        let mut data: [u8; 2] = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut data = [0u8; 6];
 
         // Read in the output data.
-        match self.wrrd(mag::MAG, &[mag::Register::TempOutH as u8], &mut data[0..1]) {
+        match self.wrrd(mag::MAG, &[mag::Register::OutXH as u8 | (1 << 7)], &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        match self.wrrd(mag::MAG, &[mag::Register::TempOutL as u8], &mut data[1..]) {
+        Ok( mag::decode(&data) )
+    }
+
+    fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.magraw()?;
+
+        self.mag_normalize(raw)
+    }
+
+    fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, Error<E>> {
+        let raw = self.magraw()?;
+
+        self.mag_fixed(raw)
+    }
+}
+
+
+impl<E, I: I2c<SevenBitAddress, Error=E>> Thermometer for Lsm303dlhc<I> {
+    type Error = Error<E>;
+    type Output = i16;
+
+    fn tempraw(&mut self) -> Result<i16, Error<E>> {
+        // Check that the temperature sensor is enabled.
+        self.temperature_enabled()?;
+
+        // Create the input buffer.
+        let mut data = [0u8; 2];
+
+        // Read in both halves in a single transaction.
+        match self.wrrd(mag::MAG, &[mag::Register::TempOutH as u8 | (1 << 7)], &mut data) {
             Err(e) => return Err( Error::BusError(e) ),
             _ => (),
         };
 
-        // Get the raw i16 data.
-        let raw = (((data[0] as i16) << 8) | (data[1] as i16)) >> 4;
+        // Get the raw 12 bit data.
+        let raw = i16::from_be_bytes(data) >> 4;
 
-        let temp = F::from(20.0) + (F::from(raw) / F::from(8.0));
+        Ok(raw)
+    }
 
-        Ok(temp)
+    fn temp<F>(&mut self) -> Result<Celsius<F>, Error<E>>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let raw = self.tempraw()?;
+
+        Ok( self.celsius(raw) )
+    }
+
+    fn temp_mc(&mut self) -> Result<MilliCelsius, Error<E>> {
+        let raw = self.tempraw()?;
+
+        Ok( self.milli_celsius(raw) )
     }
 }
 
 
 
-impl<I: Write<SevenBitAddress>> Lsm303dlhc<I> {
+impl<I: I2c<SevenBitAddress>> Lsm303dlhc<I> {
     /// Internal write function.
     #[inline(always)]
     pub(crate) fn wr(&mut self, a: SevenBitAddress, bytes: &[u8]) -> Result<(), I::Error> {
         self.interface.write(a, bytes)
     }
-}
 
-impl<I: WriteRead<SevenBitAddress>> Lsm303dlhc<I> {
     /// Internal write function.
     #[inline(always)]
     pub(crate) fn wrrd(&mut self, a: SevenBitAddress, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I::Error> {
         self.interface.write_read(a, bytes, buffer)
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::convert::Infallible;
+
+    use embedded_hal::i2c::{ ErrorType, Operation };
+
+    /// Accelerometer that holds three samples in its FIFO, the last two of
+    /// them saturated.
+    struct Fifo;
+
+    impl ErrorType for Fifo {
+        type Error = Infallible;
+    }
+
+    impl I2c for Fifo {
+        fn transaction(&mut self, _: u8, operations: &mut [Operation<'_>]) -> Result<(), Infallible> {
+            let mut reg = 0;
+
+            for op in operations {
+                match op {
+                    Operation::Write(bytes) => reg = bytes[0] & 0x7F,
+
+                    Operation::Read(buffer) => match reg {
+                        0x2F => buffer[0] = 0x03,
+
+                        0x28 => buffer.copy_from_slice(&[
+                            0x00, 0x01, 0x00, 0x00, 0x00, 0x40,
+                            0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+                            0x00, 0x00, 0xF0, 0x7F, 0x00, 0x00,
+                        ]),
+
+                        _ => buffer.fill(0),
+                    },
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fifo_read_keeps_saturated_samples() {
+        // High resolution mode, 2 g range, error policy.
+        let mut device = Lsm303dlhc::build(Fifo, Config::default());
+
+        let mut buffer = [Vector3::new(MetersPerSecondSquared(0.0f32), MetersPerSecondSquared(0.0), MetersPerSecondSquared(0.0)); 4];
+        let mut saturated = [[false; 3]; 4];
+
+        assert_eq!(device.fifo_read(&mut buffer, &mut saturated), Ok(3));
+
+        assert_eq!(saturated[..3], [[false; 3], [true, false, false], [false, true, false]]);
+
+        // 1024 LSB * 0.98 mg / LSB.
+        assert!( (buffer[0].z.0 - (1024.0 * 0.00098 * 9.80665)).abs() < 1e-4 );
+        assert!( buffer[1].x.0 < -19.0 );
+        assert!( buffer[2].y.0 >  19.0 );
+    }
+
+    #[test]
+    fn temperature_offset() {
+        let mut device = Lsm303dlhc::build((), Config::default());
+
+        assert_eq!(device.milli_celsius(0), MilliCelsius(20_000));
+
+        device.set_temperature_offset(21.8);
+        assert_eq!(device.temperature_offset(), 21.8);
+        assert_eq!(device.milli_celsius(8), MilliCelsius(22_800));
+
+        // 8 LSB / ºC.
+        device.calibrate(12, 22.4);
+        assert_eq!(device.milli_celsius(12), MilliCelsius(22_400));
+        assert_eq!(device.celsius::<f32>(12).0, 22.4);
+    }
+}
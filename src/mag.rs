@@ -3,6 +3,8 @@
 
 use core::ops::*;
 
+use crate::units::{ NanoTesla, MicroTesla, Vector3 };
+
 use micro::drivers::Data;
 
 
@@ -11,19 +13,47 @@ pub trait Magnetometer {
     type Error;
     type Output: Data;
 
-    /// Returns the raw accelerometer data.
+    /// Returns the raw magnetometer data.
     fn magraw(&mut self) -> Result<[Self::Output; 3], Self::Error>;
 
-    /// Returns the accelerometer data after a normalization process.
+    /// Returns the magnetometer data in uT after a normalization process.
     /// The associated `F` type must be an `f32` wrapper with platform support
     /// for `f32` operations.
     /// For example, if the ISA does not define float support, but the device
     /// has a propietary peripheral to operate on `f32`, this wrapper must
     /// implement this functionality.
     /// In case of hardware support for `f32` (e.g. amr-none-eabihf) `F=f32`.
-    fn mag<F>(&mut self) -> Result<[F; 3], Self::Error>
+    fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, Self::Error>
         where F: Clone + Copy +
             From<f32> + From<Self::Output> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the magnetometer data in nT with integer only arithmetic.
+    /// Applies the same saturation handling as the normalization process.
+    fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, Self::Error>;
+}
+
+
+
+/// Async version of the `Magnetometer` trait.
+/// The normalization follows the same rules as the blocking trait.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncMagnetometer {
+    type Error;
+    type Output: Data;
+
+    /// Returns the raw magnetometer data.
+    async fn magraw(&mut self) -> Result<[Self::Output; 3], Self::Error>;
+
+    /// Returns the magnetometer data in uT after a normalization process.
+    async fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, Self::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the magnetometer data in nT with integer only arithmetic.
+    async fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, Self::Error>;
 }
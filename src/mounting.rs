@@ -0,0 +1,370 @@
+//! Mounting orientation of a sensor relative to the body frame.
+//! The `Mounted` wrapper rotates the outputs of a sensor to the body frame
+//! and presents them through the same sensor traits.
+
+
+use crate::{ Accelerometer, Gyroscope, Magnetometer, Thermometer };
+use crate::units::{
+    self, Celsius, MetersPerSecondSquared, MicroTesla, MilliCelsius,
+    MilliDegreesPerSecond, MilliG, NanoTesla, RadiansPerSecond, Vector3,
+};
+
+use core::ops::*;
+
+
+
+/// Sensor axis and sign that a body axis reads from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sensor X-axis.
+    PosX,
+    /// Inverted sensor X-axis.
+    NegX,
+
+    /// Sensor Y-axis.
+    PosY,
+    /// Inverted sensor Y-axis.
+    NegY,
+
+    /// Sensor Z-axis.
+    PosZ,
+    /// Inverted sensor Z-axis.
+    NegZ,
+}
+
+impl Direction {
+    /// Returns the sensor axis index and if it is inverted.
+    #[inline]
+    fn index(&self) -> (usize, bool) {
+        match *self {
+            Direction::PosX => (0, false),
+            Direction::NegX => (0, true ),
+            Direction::PosY => (1, false),
+            Direction::NegY => (1, true ),
+            Direction::PosZ => (2, false),
+            Direction::NegZ => (2, true ),
+        }
+    }
+}
+
+
+
+/// Number of fractional bits of the fixed point matrix coefficients.
+const FRACTION: u32 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Axis permutation and sign.
+    Remap([Direction; 3]),
+
+    /// Full rotation matrix and its Q1.14 fixed point coefficients.
+    Matrix([[f32; 3]; 3], [[i32; 3]; 3]),
+}
+
+/// Rotation from the sensor frame to the body frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    kind: Kind,
+}
+
+impl Rotation {
+    /// The sensor is aligned with the body frame.
+    pub const fn identity() -> Self {
+        Rotation { kind: Kind::Remap([Direction::PosX, Direction::PosY, Direction::PosZ]) }
+    }
+
+    /// Axis permutation and sign. The body X, Y and Z axis read the given
+    /// sensor axis. The raw and fixed point outputs are remapped exactly.
+    /// Returns `None` if a sensor axis is used more than once.
+    pub fn remap(axes: [Direction; 3]) -> Option<Self> {
+        let [a, b, c] = axes.map(|d| d.index().0);
+
+        if (a == b) || (b == c) || (a == c) { return None }
+
+        Some( Rotation { kind: Kind::Remap(axes) } )
+    }
+
+    /// Full rotation matrix, where each row gives a body axis as a function
+    /// of the sensor X, Y and Z axis (`body = matrix * sensor`).
+    /// The raw and fixed point outputs are rotated with Q1.14 fixed point
+    /// coefficients and rounded to the nearest integer.
+    pub const fn matrix(matrix: [[f32; 3]; 3]) -> Self {
+        let mut fixed = [[0i32; 3]; 3];
+
+        let mut i = 0;
+        while i < 9 {
+            fixed[i / 3][i % 3] = units::round( matrix[i / 3][i % 3] * (1 << FRACTION) as f32 );
+            i += 1;
+        }
+
+        Rotation { kind: Kind::Matrix(matrix, fixed) }
+    }
+
+    /// Rotates integer data.
+    fn int(&self, v: [i32; 3]) -> [i32; 3] {
+        match self.kind {
+            Kind::Remap(axes) => axes.map(|d| {
+                let (i, neg) = d.index();
+
+                if neg { -v[i] } else { v[i] }
+            }),
+
+            Kind::Matrix(_, m) => {
+                let [x, y, z] = v.map(|a| a as i64);
+
+                m.map(|row| {
+                    let r = (row[0] as i64 * x) + (row[1] as i64 * y) + (row[2] as i64 * z);
+
+                    // Round to the nearest integer, half away from zero.
+                    let half = 1 << (FRACTION - 1);
+                    let r = if r < 0 { -((half - r) >> FRACTION) } else { (r + half) >> FRACTION };
+
+                    r.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+                })
+            },
+        }
+    }
+
+    /// Rotates raw data, saturating at the limits of the `i16` range.
+    fn raw(&self, v: [i16; 3]) -> [i16; 3] {
+        self.int( v.map(|a| a as i32) ).map(|a| a.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+    }
+
+    /// Rotates normalized data.
+    fn float<F>(&self, v: Vector3<F>) -> Vector3<F>
+        where F: Clone + Copy +
+            From<f32> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let v = [v.x, v.y, v.z];
+
+        match self.kind {
+            Kind::Remap(axes) => Vector3::from( axes.map(|d| {
+                let (i, neg) = d.index();
+
+                if neg { F::from(0.0) - v[i] } else { v[i] }
+            })),
+
+            Kind::Matrix(m, _) => Vector3::from( m.map(|row| {
+                (F::from(row[0]) * v[0]) + (F::from(row[1]) * v[1]) + (F::from(row[2]) * v[2])
+            })),
+        }
+    }
+}
+
+
+
+/// Sensor mounted with a rotation relative to the body frame.
+/// Implements the same traits as the sensor with the outputs in the body
+/// frame. Temperature readings are passed through unchanged. The saturation
+/// flags of the sensor refer to the sensor axis.
+/// The raw outputs are rotated as counts, so they are only meaningful for
+/// sensors with the same gain on every axis and without overflow sentinels.
+/// The LSM303DLHC magnetometer has a different gain on the Z-axis and reads
+/// -4096 on overflow, use the normalized or fixed point outputs instead,
+/// which are rotated after the per-axis scaling and saturation handling.
+pub struct Mounted<S> {
+    /// Mounted sensor.
+    sensor: S,
+
+    /// Rotation from the sensor frame to the body frame.
+    rotation: Rotation,
+}
+
+impl<S> Mounted<S> {
+    /// Creates a new mounted sensor.
+    pub fn new(sensor: S, rotation: Rotation) -> Self {
+        Mounted { sensor, rotation }
+    }
+
+    /// Gives access to the sensor.
+    pub fn sensor(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    /// Returns the rotation from the sensor frame to the body frame.
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Releases the sensor.
+    pub fn release(self) -> S {
+        self.sensor
+    }
+}
+
+
+
+impl<S: Accelerometer<Output = i16>> Accelerometer for Mounted<S> {
+    type Error = S::Error;
+    type Output = i16;
+
+    fn accelraw(&mut self) -> Result<[i16; 3], S::Error> {
+        Ok( self.rotation.raw( self.sensor.accelraw()? ) )
+    }
+
+    fn accel<F>(&mut self) -> Result<Vector3<MetersPerSecondSquared<F>>, S::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let data = self.sensor.accel::<F>()?.map(|a| a.0);
+
+        Ok( self.rotation.float(data).map(MetersPerSecondSquared) )
+    }
+
+    fn accel_mg(&mut self) -> Result<Vector3<MilliG>, S::Error> {
+        let data = self.sensor.accel_mg()?.map(|a| a.0);
+
+        Ok( Vector3::from( self.rotation.int(data.into()) ).map(MilliG) )
+    }
+}
+
+impl<S: Gyroscope<Output = i16>> Gyroscope for Mounted<S> {
+    type Error = S::Error;
+    type Output = i16;
+
+    fn gyroraw(&mut self) -> Result<[i16; 3], S::Error> {
+        Ok( self.rotation.raw( self.sensor.gyroraw()? ) )
+    }
+
+    fn gyro<F>(&mut self) -> Result<Vector3<RadiansPerSecond<F>>, S::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let data = self.sensor.gyro::<F>()?.map(|a| a.0);
+
+        Ok( self.rotation.float(data).map(RadiansPerSecond) )
+    }
+
+    fn gyro_mdps(&mut self) -> Result<Vector3<MilliDegreesPerSecond>, S::Error> {
+        let data = self.sensor.gyro_mdps()?.map(|a| a.0);
+
+        Ok( Vector3::from( self.rotation.int(data.into()) ).map(MilliDegreesPerSecond) )
+    }
+}
+
+impl<S: Magnetometer<Output = i16>> Magnetometer for Mounted<S> {
+    type Error = S::Error;
+    type Output = i16;
+
+    /// Rotates the raw counts. Only valid for sensors with the same gain on
+    /// every axis, see the `Mounted` documentation.
+    fn magraw(&mut self) -> Result<[i16; 3], S::Error> {
+        Ok( self.rotation.raw( self.sensor.magraw()? ) )
+    }
+
+    fn mag<F>(&mut self) -> Result<Vector3<MicroTesla<F>>, S::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        let data = self.sensor.mag::<F>()?.map(|a| a.0);
+
+        Ok( self.rotation.float(data).map(MicroTesla) )
+    }
+
+    fn mag_nt(&mut self) -> Result<Vector3<NanoTesla>, S::Error> {
+        let data = self.sensor.mag_nt()?.map(|a| a.0);
+
+        Ok( Vector3::from( self.rotation.int(data.into()) ).map(NanoTesla) )
+    }
+}
+
+impl<S: Thermometer> Thermometer for Mounted<S> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    fn tempraw(&mut self) -> Result<S::Output, S::Error> {
+        self.sensor.tempraw()
+    }
+
+    fn temp<F>(&mut self) -> Result<Celsius<F>, S::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>
+    {
+        self.sensor.temp()
+    }
+
+    fn temp_mc(&mut self) -> Result<MilliCelsius, S::Error> {
+        self.sensor.temp_mc()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remap_axes_and_sign() {
+        let rotation = Rotation::remap([Direction::NegY, Direction::PosX, Direction::NegZ]).unwrap();
+
+        assert_eq!(rotation.int([1, 2, 3]), [-2, 1, -3]);
+        assert_eq!(rotation.raw([100, -200, 300]), [200, 100, -300]);
+
+        assert_eq!(Rotation::identity().raw([1, -2, 3]), [1, -2, 3]);
+    }
+
+    #[test]
+    fn remap_rejects_repeated_axis() {
+        assert!( Rotation::remap([Direction::PosX, Direction::NegX, Direction::PosZ]).is_none() );
+        assert!( Rotation::remap([Direction::PosZ, Direction::PosY, Direction::NegZ]).is_none() );
+    }
+
+    #[test]
+    fn remap_saturates_raw() {
+        let rotation = Rotation::remap([Direction::NegX, Direction::NegY, Direction::PosZ]).unwrap();
+
+        assert_eq!(rotation.raw([i16::MIN, i16::MAX, i16::MIN]), [i16::MAX, -i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn matrix_rounds_to_nearest() {
+        let rotation = Rotation::matrix([
+            [0.5, 0.0, 0.0],
+            [0.0, 0.25, 0.0],
+            [0.0, 0.0, -1.0],
+        ]);
+
+        assert_eq!(rotation.int([3, 6, 7]), [2, 2, -7]);
+        assert_eq!(rotation.int([-3, -6, -7]), [-2, -2, 7]);
+    }
+
+    #[test]
+    fn matrix_saturates_raw() {
+        // 45º rotation around the Z-axis.
+        let c = core::f32::consts::FRAC_1_SQRT_2;
+
+        let rotation = Rotation::matrix([
+            [c,  -c,  0.0],
+            [c,   c,  0.0],
+            [0.0, 0.0, 1.0],
+        ]);
+
+        assert_eq!(rotation.raw([i16::MAX, i16::MIN, 0]), [i16::MAX, -1, 0]);
+        assert_eq!(rotation.raw([i16::MIN, i16::MAX, 0]), [i16::MIN, -1, 0]);
+
+        // The fixed point path does not saturate at the i16 range.
+        assert_eq!(rotation.int([1000, 1000, -5]), [0, 1414, -5]);
+    }
+
+    #[test]
+    fn matrix_float() {
+        let rotation = Rotation::matrix([
+            [0.0, 1.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]);
+
+        assert_eq!(rotation.float(Vector3::new(1.0f32, 2.0, 3.0)), Vector3::new(2.0, -1.0, 3.0));
+    }
+}
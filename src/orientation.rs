@@ -0,0 +1,100 @@
+//! Orientation (portrait / landscape) recognition.
+
+
+use crate::Accelerometer;
+
+
+
+/// Orientation of a device given by the axis that points up.
+/// The accelerometer reads +1 g on the axis pointing away from the ground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// The Z-axis points up.
+    FaceUp,
+
+    /// The Z-axis points down.
+    FaceDown,
+
+    /// The Y-axis points up.
+    PortraitUp,
+
+    /// The Y-axis points down.
+    PortraitDown,
+
+    /// The X-axis points down.
+    LandscapeLeft,
+
+    /// The X-axis points up.
+    LandscapeRight,
+
+    /// No axis is close enough to the vertical.
+    Unknown,
+}
+
+impl Orientation {
+    /// Computes the orientation in software from a raw accelerometer sample.
+    /// An axis is recognized when it is within 45 degrees of the vertical.
+    /// In 4D mode the Z-axis is not recognized and only the portrait and
+    /// landscape orientations are reported, a device lying flat is
+    /// `Unknown`.
+    pub fn from_raw(raw: [i16; 3], fourd: bool) -> Orientation {
+        let [x, y, z] = raw.map(|v| v as i64);
+
+        // Squared magnitude of the sample.
+        let mag = (x * x) + (y * y) + (z * z);
+
+        // The axis must hold more than half of the squared magnitude.
+        let vertical = |v: i64| 2 * v * v > mag;
+
+        match (vertical(x), vertical(y), !fourd && vertical(z)) {
+            (true, _, _) => if x > 0 { Orientation::LandscapeRight } else { Orientation::LandscapeLeft },
+            (_, true, _) => if y > 0 { Orientation::PortraitUp     } else { Orientation::PortraitDown  },
+            (_, _, true) => if z > 0 { Orientation::FaceUp         } else { Orientation::FaceDown      },
+
+            _ => Orientation::Unknown,
+        }
+    }
+
+    /// Reads the raw data of the given accelerometer and computes the
+    /// orientation in software.
+    /// Use this with devices without hardware orientation recognition.
+    pub fn read<A: Accelerometer<Output=i16>>(accel: &mut A, fourd: bool) -> Result<Orientation, A::Error> {
+        Ok( Orientation::from_raw(accel.accelraw()?, fourd) )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_raw_6d() {
+        assert_eq!(Orientation::from_raw([   0,     0,  1000], false), Orientation::FaceUp);
+        assert_eq!(Orientation::from_raw([ 100,  -200, -1000], false), Orientation::FaceDown);
+        assert_eq!(Orientation::from_raw([ 100,  1000,   200], false), Orientation::PortraitUp);
+        assert_eq!(Orientation::from_raw([   0, -1000,     0], false), Orientation::PortraitDown);
+        assert_eq!(Orientation::from_raw([-1000,    0,   300], false), Orientation::LandscapeLeft);
+        assert_eq!(Orientation::from_raw([ 1000,  300,     0], false), Orientation::LandscapeRight);
+    }
+
+    #[test]
+    fn from_raw_unknown() {
+        // 45 degrees between the X and Z axis.
+        assert_eq!(Orientation::from_raw([1000, 0, 1000], false), Orientation::Unknown);
+        assert_eq!(Orientation::from_raw([600, 600, 600], false), Orientation::Unknown);
+        assert_eq!(Orientation::from_raw([0, 0, 0], false), Orientation::Unknown);
+    }
+
+    #[test]
+    fn from_raw_4d() {
+        // The Z-axis is not recognized, a tilted device lying flat is unknown.
+        assert_eq!(Orientation::from_raw([  0,    0, 1000], true), Orientation::Unknown);
+        assert_eq!(Orientation::from_raw([100, -300, 1000], true), Orientation::Unknown);
+        assert_eq!(Orientation::from_raw([0, 0, -1000], true), Orientation::Unknown);
+
+        assert_eq!(Orientation::from_raw([100, -1000, 300], true), Orientation::PortraitDown);
+        assert_eq!(Orientation::from_raw([i16::MIN, 0, i16::MAX / 2], true), Orientation::LandscapeLeft);
+    }
+}
@@ -0,0 +1,14 @@
+//! Data-ready aware sensor readings.
+
+
+
+/// A new sample read after checking the data-ready status of the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    /// Sample data. Raw samples are `[i16; 3]` and normalized samples are a
+    /// `Vector3` of the unit of the sensor.
+    pub data: T,
+
+    /// At least one sample was overwritten before being read.
+    pub overrun: bool,
+}
@@ -0,0 +1,87 @@
+//! Handling of saturated sensor outputs.
+
+
+
+/// Policy applied when an axis of a sensor output is saturated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Saturation {
+    /// Discard the sample and return a range overflow error.
+    /// This is the default policy.
+    Error,
+
+    /// Clamp the saturated axis to the limit of the range and flag it.
+    Clamp,
+
+    /// Return the sample as read and flag the saturated axis.
+    Flag,
+}
+
+impl Saturation {
+    /// Applies the policy to the raw data, where `min` and `max` are the
+    /// lowest and highest values that are not saturated.
+    /// Returns the per-axis saturation flags, or the index of the first
+    /// saturated axis with the `Error` policy.
+    pub(crate) fn apply(&self, raw: &mut [i16; 3], min: i16, max: i16) -> Result<[bool; 3], usize> {
+        let mut flags = [false; 3];
+
+        for (i, value) in raw.iter_mut().enumerate() {
+            if (*value >= min) && (*value <= max) { continue }
+
+            match *self {
+                Saturation::Error => return Err(i),
+                Saturation::Clamp => *value = if *value > max { max } else { min },
+                Saturation::Flag  => (),
+            }
+
+            flags[i] = true;
+        }
+
+        Ok(flags)
+    }
+}
+
+impl const Default for Saturation {
+    fn default() -> Self {
+        Saturation::Error
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_within_range() {
+        for policy in [Saturation::Error, Saturation::Clamp, Saturation::Flag] {
+            let mut raw = [-2048, 0, 2047];
+
+            assert_eq!(policy.apply(&mut raw, -2048, 2047), Ok([false; 3]));
+            assert_eq!(raw, [-2048, 0, 2047]);
+        }
+    }
+
+    #[test]
+    fn apply_error() {
+        let mut raw = [0, -4096, 4096];
+
+        assert_eq!(Saturation::Error.apply(&mut raw, -2048, 2047), Err(1));
+    }
+
+    #[test]
+    fn apply_clamp() {
+        let mut raw = [100, -4096, 2048];
+
+        assert_eq!(Saturation::Clamp.apply(&mut raw, -2048, 2047), Ok([false, true, true]));
+        assert_eq!(raw, [100, -2048, 2047]);
+    }
+
+    #[test]
+    fn apply_flag() {
+        let mut raw = [32767, 0, -32768];
+
+        assert_eq!(Saturation::Flag.apply(&mut raw, -32759, 32759), Ok([true, false, true]));
+        assert_eq!(raw, [32767, 0, -32768]);
+    }
+}
@@ -0,0 +1,58 @@
+//! Results of the built-in self-test routines.
+
+
+
+/// Per-axis report of a self-test routine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTest {
+    /// Absolute output change of each axis with the self-test enabled.
+    /// Given in the physical unit of the sensor (e.g. dps for gyroscopes).
+    pub delta: [f32; 3],
+
+    /// The output change of the axis is within the given limits.
+    pub pass: [bool; 3],
+}
+
+impl SelfTest {
+    /// Builds the report from the averaged outputs with the self-test
+    /// disabled and enabled, already in physical units, and the minimum
+    /// and maximum accepted output change.
+    pub(crate) fn new(base: [f32; 3], test: [f32; 3], limits: (f32, f32)) -> Self {
+        let mut report = SelfTest { delta: [0.0; 3], pass: [false; 3] };
+
+        for i in 0..3 {
+            let delta = test[i] - base[i];
+            let delta = if delta < 0.0 { -delta } else { delta };
+
+            report.delta[i] = delta;
+            report.pass[i] = (delta >= limits.0) && (delta <= limits.1);
+        }
+
+        report
+    }
+
+    /// Returns `true` if all the axis passed the self-test.
+    pub fn passed(&self) -> bool {
+        self.pass.iter().all(|p| *p)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdict() {
+        let report = SelfTest::new([1.0, -2.0, 0.5], [131.0, -232.0, 10.5], (100.0, 300.0));
+
+        assert_eq!(report.delta, [130.0, 230.0, 10.0]);
+        assert_eq!(report.pass, [true, true, false]);
+        assert!( !report.passed() );
+
+        let report = SelfTest::new([0.0; 3], [100.0, -300.0, 200.0], (100.0, 300.0));
+
+        assert!( report.passed() );
+    }
+}
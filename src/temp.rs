@@ -3,6 +3,8 @@
 
 use core::ops::*;
 
+use crate::units::{ Celsius, MilliCelsius };
+
 use micro::drivers::Data;
 
 
@@ -11,19 +13,49 @@ pub trait Thermometer {
     type Error;
     type Output: Data;
 
-    /// Returns the raw accelerometer data.
+    /// Returns the raw temperature data.
     fn tempraw(&mut self) -> Result<Self::Output, Self::Error>;
 
-    /// Returns the accelerometer data after a normalization process.
+    /// Returns the temperature in ºC after a normalization process.
+    /// The sensors have no absolute reference, the result depends on the
+    /// temperature offset of each driver. The default offset is a nominal
+    /// value, calibrate it against a known temperature for accurate results.
     /// The associated `F` type must be an `f32` wrapper with platform support
     /// for `f32` operations.
     /// For example, if the ISA does not define float support, but the device
     /// has a propietary peripheral to operate on `f32`, this wrapper must
     /// implement this functionality.
     /// In case of hardware support for `f32` (e.g. amr-none-eabihf) `F=f32`.
-    fn temp<F>(&mut self) -> Result<F, Self::Error>
+    fn temp<F>(&mut self) -> Result<Celsius<F>, Self::Error>
         where F: Clone + Copy +
             From<f32> + From<Self::Output> +
             Add<F, Output=F> + Sub<F, Output=F> +
             Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the temperature in mºC with integer only arithmetic.
+    fn temp_mc(&mut self) -> Result<MilliCelsius, Self::Error>;
+}
+
+
+
+/// Async version of the `Thermometer` trait.
+/// The normalization follows the same rules as the blocking trait.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncThermometer {
+    type Error;
+    type Output: Data;
+
+    /// Returns the raw temperature data.
+    async fn tempraw(&mut self) -> Result<Self::Output, Self::Error>;
+
+    /// Returns the temperature in ºC after a normalization process.
+    async fn temp<F>(&mut self) -> Result<Celsius<F>, Self::Error>
+        where F: Clone + Copy +
+            From<f32> + From<Self::Output> +
+            Add<F, Output=F> + Sub<F, Output=F> +
+            Mul<F, Output=F> + Div<F, Output=F>;
+
+    /// Returns the temperature in mºC with integer only arithmetic.
+    async fn temp_mc(&mut self) -> Result<MilliCelsius, Self::Error>;
 }
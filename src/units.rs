@@ -0,0 +1,250 @@
+//! Physical units of the normalized sensor outputs.
+//! Each floating point unit wraps the `f32` wrapper type `F` used by the
+//! normalization, and each fixed point unit wraps an integer amount of
+//! milli or nano units, so mixing quantities of different units is a compile
+//! error.
+
+
+use core::ops::*;
+
+
+
+/// X, Y and Z axis values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector3<T> {
+    /// X-axis value.
+    pub x: T,
+
+    /// Y-axis value.
+    pub y: T,
+
+    /// Z-axis value.
+    pub z: T,
+}
+
+impl<T> Vector3<T> {
+    /// Static initializer.
+    #[inline(always)]
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    /// Applies the function to each axis.
+    #[inline]
+    pub fn map<U, M: FnMut(T) -> U>(self, mut f: M) -> Vector3<U> {
+        Vector3 { x: f(self.x), y: f(self.y), z: f(self.z) }
+    }
+}
+
+impl<T> core::convert::From<[T; 3]> for Vector3<T> {
+    fn from(s: [T; 3]) -> Vector3<T> {
+        let [x, y, z] = s;
+
+        Vector3 { x, y, z }
+    }
+}
+
+impl<T> core::convert::From<Vector3<T>> for [T; 3] {
+    fn from(s: Vector3<T>) -> [T; 3] {
+        [s.x, s.y, s.z]
+    }
+}
+
+
+
+/// Declares a unit newtype with the arithmetic that keeps the unit.
+macro_rules! unit {
+    ($(#[$meta:meta])* $name:ident: $int:ty) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+        pub struct $name(pub $int);
+
+        impl $name {
+            /// Returns the inner value.
+            #[inline(always)]
+            pub fn value(self) -> $int {
+                self.0
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            #[inline(always)]
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            #[inline(always)]
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+    };
+
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name<F>(pub F);
+
+        impl<F> $name<F> {
+            /// Returns the inner value.
+            #[inline(always)]
+            pub fn value(self) -> F {
+                self.0
+            }
+        }
+
+        impl<F: Add<F, Output=F>> Add for $name<F> {
+            type Output = $name<F>;
+
+            #[inline(always)]
+            fn add(self, rhs: $name<F>) -> $name<F> {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl<F: Sub<F, Output=F>> Sub for $name<F> {
+            type Output = $name<F>;
+
+            #[inline(always)]
+            fn sub(self, rhs: $name<F>) -> $name<F> {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl<F: Mul<F, Output=F>> Mul<F> for $name<F> {
+            type Output = $name<F>;
+
+            #[inline(always)]
+            fn mul(self, rhs: F) -> $name<F> {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl<F: Div<F, Output=F>> Div<F> for $name<F> {
+            type Output = $name<F>;
+
+            #[inline(always)]
+            fn div(self, rhs: F) -> $name<F> {
+                $name(self.0 / rhs)
+            }
+        }
+    };
+}
+
+unit!(
+    /// Acceleration in m/s^2.
+    MetersPerSecondSquared
+);
+
+unit!(
+    /// Angular rate in rad/s.
+    RadiansPerSecond
+);
+
+unit!(
+    /// Magnetic field in uT (gauss * 100).
+    MicroTesla
+);
+
+unit!(
+    /// Temperature in ºC.
+    Celsius
+);
+
+unit!(
+    /// Fixed point acceleration in mg.
+    MilliG: i32
+);
+
+unit!(
+    /// Fixed point angular rate in mdps.
+    MilliDegreesPerSecond: i32
+);
+
+unit!(
+    /// Fixed point magnetic field in nT.
+    NanoTesla: i32
+);
+
+unit!(
+    /// Fixed point temperature in mºC.
+    MilliCelsius: i32
+);
+
+
+
+/// Scales the raw value by `num / den` with integer arithmetic, rounding to
+/// the nearest integer.
+#[inline]
+pub(crate) fn scale(raw: i32, num: i32, den: i32) -> i32 {
+    let x = raw * num;
+
+    if x < 0 { (x - (den / 2)) / den }
+    else { (x + (den / 2)) / den }
+}
+
+/// Rounds the value to the nearest integer, half away from zero.
+/// Values out of the `i32` range saturate.
+#[inline]
+pub(crate) const fn round(x: f32) -> i32 {
+    if x < 0.0 { (x - 0.5) as i32 }
+    else { (x + 0.5) as i32 }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_rounds_to_nearest() {
+        // 8.75 mdps / LSB.
+        assert_eq!(scale(  1, 35, 4),   9);
+        assert_eq!(scale(  2, 35, 4),  18);
+        assert_eq!(scale(100, 35, 4), 875);
+
+        assert_eq!(scale(  -1, 35, 4),   -9);
+        assert_eq!(scale(  -2, 35, 4),  -18);
+        assert_eq!(scale(-100, 35, 4), -875);
+    }
+
+    #[test]
+    fn scale_rounds_half_away_from_zero() {
+        assert_eq!(scale( 1, 1, 2),  1);
+        assert_eq!(scale(-1, 1, 2), -1);
+        assert_eq!(scale( 3, 1, 2),  2);
+        assert_eq!(scale(-3, 1, 2), -2);
+
+        // 1.95 mg / LSB.
+        assert_eq!(scale( 10, 1950, 1000),  20);
+        assert_eq!(scale(-10, 1950, 1000), -20);
+        assert_eq!(scale(  3, 1950, 1000),   6);
+        assert_eq!(scale( -3, 1950, 1000),  -6);
+    }
+
+    #[test]
+    fn round_half_away_from_zero() {
+        assert_eq!(round( 0.4),  0);
+        assert_eq!(round( 0.5),  1);
+        assert_eq!(round(-0.4),  0);
+        assert_eq!(round(-0.5), -1);
+
+        assert_eq!(round( 21.8 * 1000.0),  21_800);
+        assert_eq!(round(-21.8 * 1000.0), -21_800);
+        assert_eq!(round( 1.0e12), i32::MAX);
+    }
+
+    #[test]
+    fn scale_exact() {
+        assert_eq!(scale( 2047, 100_000, 1100),  186_091);
+        assert_eq!(scale(-2048, 100_000, 1100), -186_182);
+        assert_eq!(scale(0, 70, 1), 0);
+    }
+}